@@ -0,0 +1,83 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::rc::Rc;
+
+use glib::error;
+
+use password::to_glib_error;
+use Service;
+use SessionPolicy;
+
+// The checks a service runs before a secret is sent or requested through it. An object which is
+// not attached to a service cannot be checked, so the operation then runs directly.
+pub fn check_access<T: 'static, O, F>(service: Option<&Service>, operation: O, callback: F)
+    where O: Fn(Box<Fn(Result<T, error::Error>)>) + 'static,
+          F: Fn(Result<T, error::Error>) + 'static,
+{
+    let service =
+        match service {
+            Some(service) => service.clone(),
+            None => return operation(Box::new(callback)),
+        };
+    let policy = service.get_session_policy();
+    let callback = Rc::new(callback);
+    let verify_owner = {
+        let service = service.clone();
+        let callback = callback.clone();
+        let operation = Rc::new(operation);
+        move || {
+            let callback = callback.clone();
+            let operation = operation.clone();
+            service.verify_owner(move |result| {
+                match result {
+                    Ok(_) => {
+                        let callback = callback.clone();
+                        operation(Box::new(move |result: Result<T, error::Error>| callback(result)));
+                    },
+                    Err(error) => callback(Err(to_glib_error(error))),
+                }
+            });
+        }
+    };
+    if policy == SessionPolicy::Any {
+        return verify_owner();
+    }
+    service.check_session(policy, move |result| {
+        match result {
+            Ok(_) => verify_owner(),
+            Err(error) => callback(Err(to_glib_error(error))),
+        }
+    });
+}
+
+pub fn check_access_sync(service: Option<&Service>) -> Result<(), error::Error> {
+    let service =
+        match service {
+            Some(service) => service,
+            None => return Ok(()),
+        };
+    let policy = service.get_session_policy();
+    if policy != SessionPolicy::Any {
+        service.check_session_sync(policy).map_err(to_glib_error)?;
+    }
+    service.verify_owner_sync().map_err(to_glib_error)
+}
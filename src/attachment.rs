@@ -0,0 +1,348 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use glib::error;
+use glib::translate::{ToGlibPtr, from_glib_full, from_glib_none};
+use glib_ffi;
+
+use Error::IsLocked;
+use error::new_error;
+use Item;
+use item::delete_items;
+use PasswordError::{self, CorruptedAttachment, Glib, NoResult};
+use Schema;
+use Value;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+const ATTACHMENT_ATTRIBUTE: &'static str = "attachment";
+const CHECKSUM_ATTRIBUTE: &'static str = "attachment-checksum";
+const CHUNK_ATTRIBUTE: &'static str = "attachment-chunk";
+const CHUNKS_ATTRIBUTE: &'static str = "attachment-chunks";
+const GENERATION_ATTRIBUTE: &'static str = "attachment-generation";
+
+const CHUNK_ATTRIBUTES: [&'static str; 4] = [CHECKSUM_ATTRIBUTE, CHUNK_ATTRIBUTE, CHUNKS_ATTRIBUTE, GENERATION_ATTRIBUTE];
+
+pub struct Chunk {
+    pub attributes: HashMap<String, String>,
+    pub label: String,
+    pub value: Value,
+}
+
+type ChunkCallback = Box<Fn(Result<bool, error::Error>)>;
+type SearchCallback = Box<Fn(Result<Vec<Item>, PasswordError>)>;
+type StoreChunk = Fn(&Chunk, ChunkCallback);
+
+// The chunk attributes are not part of the schema, so the chunks are stored without one and
+// the schema name is matched as a regular attribute instead.
+pub fn attachment_attributes(schema: &Schema, attributes: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut attributes = attributes.clone();
+    let schema_name = unsafe { from_glib_none((*schema.to_glib_none().0).name) };
+    attributes.insert("xdg:schema".to_string(), schema_name);
+    attributes.insert(ATTACHMENT_ATTRIBUTE.to_string(), "true".to_string());
+    attributes
+}
+
+pub fn clear<Q>(search_chunks: Q, attributes: &HashMap<String, String>, callback: Box<Fn(Result<bool, PasswordError>)>)
+    where Q: Fn(&HashMap<String, String>, SearchCallback)
+{
+    let chunk_attributes = attributes.clone();
+    let callback = Rc::new(callback);
+    search_chunks(attributes, Box::new(move |items: Result<Vec<Item>, PasswordError>| {
+        match items {
+            Ok(items) => {
+                let callback = callback.clone();
+                delete_items(chunks_of(items, &chunk_attributes), move |result| callback(result.map_err(Glib)));
+            },
+            Err(error) => callback(Err(error)),
+        }
+    }));
+}
+
+pub fn join(items: Vec<Item>, attributes: &HashMap<String, String>) -> Result<Vec<u8>, PasswordError> {
+    let items = chunks_of(items, attributes);
+    if items.is_empty() {
+        return Err(NoResult);
+    }
+    if items.iter().any(|item| item.get_locked()) {
+        return Err(Glib(new_error(IsLocked, "The attachment is locked")));
+    }
+    let mut generations: HashMap<String, (u64, Vec<(HashMap<String, String>, Option<Vec<u8>>)>)> = HashMap::new();
+    for item in items {
+        let chunk_attributes = item.get_attributes();
+        let generation = chunk_attributes.get(GENERATION_ATTRIBUTE).cloned().unwrap_or_default();
+        let generation = generations.entry(generation).or_insert((0, vec![]));
+        generation.0 = generation.0.max(item.get_created());
+        generation.1.push((chunk_attributes, item.get_secret().map(|value| value.get())));
+    }
+    // A replacement interrupted before removing the previous generation leaves both behind:
+    // the newest complete one is the current attachment.
+    let mut generations: Vec<_> = generations.into_iter().map(|(_, generation)| generation).collect();
+    generations.sort_by(|first, second| second.0.cmp(&first.0));
+    generations.into_iter()
+        .filter_map(|(_, chunks)| assemble(chunks).ok())
+        .next()
+        .ok_or(CorruptedAttachment)
+}
+
+// The secrets are loaded one chunk at a time, so that the whole attachment never travels in a
+// single D-Bus reply.
+pub fn lookup<Q>(search_chunks: Q, attributes: &HashMap<String, String>, callback: Box<Fn(Result<Vec<u8>, PasswordError>)>)
+    where Q: Fn(&HashMap<String, String>, SearchCallback)
+{
+    let chunk_attributes = attributes.clone();
+    let callback = Rc::new(callback);
+    search_chunks(attributes, Box::new(move |items: Result<Vec<Item>, PasswordError>| {
+        let items =
+            match items {
+                Ok(items) => chunks_of(items, &chunk_attributes),
+                Err(error) => return callback(Err(error)),
+            };
+        // A locked chunk cannot be loaded: join reports it.
+        if items.iter().any(|item| item.get_locked()) {
+            return callback(join(items, &chunk_attributes));
+        }
+        let items = Rc::new(items);
+        let loaded_items = items.clone();
+        let attributes = chunk_attributes.clone();
+        let callback = callback.clone();
+        load_secrets_from(items, 0, Rc::new(move |result: Result<bool, error::Error>| {
+            match result {
+                Ok(_) => callback(join((*loaded_items).clone(), &attributes)),
+                Err(error) => callback(Err(Glib(error))),
+            }
+        }));
+    }));
+}
+
+pub fn new_generation() -> String {
+    (0..4).map(|_| format!("{:08x}", unsafe { glib_ffi::g_random_int() })).collect()
+}
+
+// The new chunks are written under a new generation before the previous ones are deleted, so
+// that a failure part way through leaves the previous attachment readable.
+pub fn replace<S, Q>(store_chunk: S, search_chunks: Q, attributes: &HashMap<String, String>, label: &str, data: &[u8], callback: Box<Fn(Result<bool, PasswordError>)>)
+    where S: Fn(&Chunk, ChunkCallback) + 'static,
+          Q: Fn(&HashMap<String, String>, SearchCallback) + 'static,
+{
+    let generation = new_generation();
+    let chunks = Rc::new(split(attributes, &generation, label, data));
+    let attributes = attributes.clone();
+    let search_chunks = Rc::new(search_chunks);
+    let callback = Rc::new(callback);
+    let stored: Box<Fn(Result<bool, PasswordError>)> = Box::new(move |result: Result<bool, PasswordError>| {
+        let chunk_attributes = attributes.clone();
+        let generation = generation.clone();
+        let callback = callback.clone();
+        search_chunks(&attributes, Box::new(move |items: Result<Vec<Item>, PasswordError>| {
+            match items {
+                Ok(items) => {
+                    // Once stored, the previous generations are stale; otherwise the partial new one is.
+                    let stale = chunks_of(items, &chunk_attributes).into_iter()
+                        .filter(|item| (item.get_attributes().get(GENERATION_ATTRIBUTE) == Some(&generation)) != result.is_ok())
+                        .collect();
+                    let result = result.clone();
+                    let callback = callback.clone();
+                    delete_items(stale, move |deleted| {
+                        callback(result.clone().and_then(|_| deleted.map(|_| true).map_err(Glib)))
+                    });
+                },
+                Err(error) => callback(result.clone().and(Err(error))),
+            }
+        }));
+    });
+    store_chunks_from(Rc::new(store_chunk), chunks, 0, Rc::new(stored));
+}
+
+pub fn split(attributes: &HashMap<String, String>, generation: &str, label: &str, data: &[u8]) -> Vec<Chunk> {
+    let checksum = checksum(data);
+    let mut parts: Vec<&[u8]> = data.chunks(CHUNK_SIZE).collect();
+    if parts.is_empty() {
+        parts.push(data);
+    }
+    let count = parts.len();
+    parts.into_iter().enumerate().map(|(index, part)| {
+        let mut attributes = attributes.clone();
+        attributes.insert(CHECKSUM_ATTRIBUTE.to_string(), checksum.clone());
+        attributes.insert(CHUNK_ATTRIBUTE.to_string(), index.to_string());
+        attributes.insert(CHUNKS_ATTRIBUTE.to_string(), count.to_string());
+        attributes.insert(GENERATION_ATTRIBUTE.to_string(), generation.to_string());
+        Chunk {
+            attributes: attributes,
+            label: format!("{} ({}/{})", label, index + 1, count),
+            value: Value::new(part, "application/octet-stream"),
+        }
+    }).collect()
+}
+
+fn assemble(chunks: Vec<(HashMap<String, String>, Option<Vec<u8>>)>) -> Result<Vec<u8>, PasswordError> {
+    let count = chunks.first().and_then(|&(ref attributes, _)| attributes.get(CHUNKS_ATTRIBUTE).cloned());
+    let expected_checksum = chunks.first().and_then(|&(ref attributes, _)| attributes.get(CHECKSUM_ATTRIBUTE).cloned());
+    let mut parts = vec![];
+    for (attributes, secret) in chunks {
+        if attributes.get(CHUNKS_ATTRIBUTE) != count.as_ref() || attributes.get(CHECKSUM_ATTRIBUTE) != expected_checksum.as_ref() {
+            return Err(CorruptedAttachment);
+        }
+        let index = attributes.get(CHUNK_ATTRIBUTE).and_then(|index| index.parse::<usize>().ok());
+        match (index, secret) {
+            (Some(index), Some(secret)) => parts.push((index, secret)),
+            _ => return Err(CorruptedAttachment),
+        }
+    }
+    parts.sort_by_key(|&(index, _)| index);
+    let count = count.and_then(|count| count.parse().ok());
+    if count != Some(parts.len()) || parts.iter().enumerate().any(|(position, &(index, _))| position != index) {
+        return Err(CorruptedAttachment);
+    }
+    let data: Vec<u8> = parts.into_iter().flat_map(|(_, part)| part).collect();
+    if Some(checksum(&data)) != expected_checksum {
+        return Err(CorruptedAttachment);
+    }
+    Ok(data)
+}
+
+fn checksum(data: &[u8]) -> String {
+    unsafe {
+        from_glib_full(glib_ffi::g_compute_checksum_for_data(glib_ffi::G_CHECKSUM_SHA256, data.as_ptr(), data.len()))
+    }
+}
+
+// A search also matches the attachments whose attributes are a superset of the requested ones.
+fn chunks_of(items: Vec<Item>, attributes: &HashMap<String, String>) -> Vec<Item> {
+    items.into_iter().filter(|item| is_chunk_of(&item.get_attributes(), attributes)).collect()
+}
+
+fn is_chunk_of(chunk_attributes: &HashMap<String, String>, attributes: &HashMap<String, String>) -> bool {
+    let mut chunk_attributes = chunk_attributes.clone();
+    for attribute in &CHUNK_ATTRIBUTES {
+        chunk_attributes.remove(*attribute);
+    }
+    chunk_attributes == *attributes
+}
+
+fn load_secrets_from(items: Rc<Vec<Item>>, index: usize, callback: Rc<Fn(Result<bool, error::Error>)>) {
+    if index == items.len() {
+        return callback(Ok(true));
+    }
+    let next_items = items.clone();
+    items[index].load_secret(move |result| {
+        match result {
+            Ok(_) => load_secrets_from(next_items.clone(), index + 1, callback.clone()),
+            Err(error) => callback(Err(error)),
+        }
+    });
+}
+
+fn store_chunks_from(store_chunk: Rc<StoreChunk>, chunks: Rc<Vec<Chunk>>, index: usize, callback: Rc<Box<Fn(Result<bool, PasswordError>)>>) {
+    if index == chunks.len() {
+        return callback(Ok(true));
+    }
+    let next_store_chunk = store_chunk.clone();
+    let next_chunks = chunks.clone();
+    store_chunk(&chunks[index], Box::new(move |result: Result<bool, error::Error>| {
+        match result {
+            Ok(_) => store_chunks_from(next_store_chunk.clone(), next_chunks.clone(), index + 1, callback.clone()),
+            Err(error) => callback(Err(Glib(error))),
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use PasswordError::CorruptedAttachment;
+    use super::{CHUNK_SIZE, Chunk, GENERATION_ATTRIBUTE, assemble, is_chunk_of, split};
+
+    fn attributes() -> HashMap<String, String> {
+        let mut attributes = HashMap::new();
+        attributes.insert("xdg:schema".to_string(), "org.example.Schema".to_string());
+        attributes.insert("attachment".to_string(), "true".to_string());
+        attributes
+    }
+
+    fn stored(chunks: Vec<Chunk>) -> Vec<(HashMap<String, String>, Option<Vec<u8>>)> {
+        chunks.into_iter().map(|chunk| (chunk.attributes, Some(chunk.value.get()))).collect()
+    }
+
+    fn is_corrupted(chunks: Vec<(HashMap<String, String>, Option<Vec<u8>>)>) -> bool {
+        match assemble(chunks) {
+            Err(CorruptedAttachment) => true,
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn split_and_assemble_round_trip() {
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 10).map(|index| index as u8).collect();
+        let chunks = split(&attributes(), "generation", "label", &data);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].label, "label (1/3)");
+        let mut chunks = stored(chunks);
+        chunks.reverse();
+        assert_eq!(assemble(chunks).unwrap(), data);
+    }
+
+    #[test]
+    fn empty_data_round_trips() {
+        let chunks = split(&attributes(), "generation", "label", &[]);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(assemble(stored(chunks)).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn missing_chunk_is_corrupted() {
+        let data = vec![1; CHUNK_SIZE + 1];
+        let mut chunks = stored(split(&attributes(), "generation", "label", &data));
+        chunks.pop();
+        assert!(is_corrupted(chunks));
+    }
+
+    #[test]
+    fn modified_chunk_is_corrupted() {
+        let mut chunks = stored(split(&attributes(), "generation", "label", b"secret"));
+        chunks[0].1 = Some(b"Secret".to_vec());
+        assert!(is_corrupted(chunks));
+    }
+
+    #[test]
+    fn locked_chunk_is_corrupted() {
+        let mut chunks = stored(split(&attributes(), "generation", "label", b"secret"));
+        chunks[0].1 = None;
+        assert!(is_corrupted(chunks));
+    }
+
+    #[test]
+    fn chunk_matches_only_its_attachment() {
+        let chunks = split(&attributes(), "generation", "label", b"secret");
+        assert_eq!(chunks[0].attributes[GENERATION_ATTRIBUTE], "generation");
+        assert!(is_chunk_of(&chunks[0].attributes, &attributes()));
+        let mut other_attributes = attributes();
+        other_attributes.insert("account".to_string(), "user".to_string());
+        assert!(!is_chunk_of(&chunks[0].attributes, &other_attributes));
+        let mut superset = chunks[0].attributes.clone();
+        superset.insert("account".to_string(), "user".to_string());
+        assert!(!is_chunk_of(&superset, &attributes()));
+    }
+}
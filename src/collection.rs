@@ -23,34 +23,183 @@ use std::collections::HashMap;
 use std::ffi::CString;
 use std::mem::transmute;
 use std::ptr::null_mut;
+use std::rc::Rc;
+use std::sync::mpsc::{Receiver, channel};
+use std::vec::IntoIter;
 
 use gio_sys;
 use glib::error;
 use glib::translate::{FromGlib, ToGlib, ToGlibPtr, from_glib_full, from_glib_none};
 use glib_ffi;
-use gobject_ffi;
-use libc::c_void;
+use gobject_ffi::{self, GCallback};
+use libc::{c_char, c_void};
 
+use access::check_access;
 use AsyncReadyCallback;
+use attachment::{self, attachment_attributes};
 use ffi;
 use Collection;
+use CollectionCreateFlags;
+use CollectionFlags;
+use Error::NoSuchObject;
+use error::{from_glib_object_error, new_error};
 use Item;
+use ITEM_CREATE_NONE;
+use ITEM_NONE;
 use ITEM_CREATE_REPLACE;
+use PasswordError::{self, Glib};
 use Schema;
+use SearchFlags;
+use SearchOptions;
+use SecretObject;
+use Service;
+use signal::{SignalHandler, connect, connect_notify};
 use SEARCH_ALL;
-use SEARCH_LOAD_SECRETS;
 use SEARCH_UNLOCK;
+use TempItem;
+use Value;
+use Variant;
+use self::CollectionEvent::*;
 use to_glib_string_hash_map;
 use COLLECTION_CREATE_NONE;
+use COLLECTION_LOAD_ITEMS;
+
+pub enum CollectionEvent {
+    ItemChanged(Item),
+    ItemCreated(Item),
+    // A deleted item cannot be loaded anymore, so the item is only there if it was loaded before.
+    ItemDeleted(String, Option<Item>),
+    LoadFailed(String, error::Error),
+}
+
+pub struct CollectionEvents {
+    _handler: SignalHandler,
+    receiver: Receiver<CollectionEvent>,
+}
+
+impl CollectionEvents {
+    // The events are received from the main loop on the calling thread, so waiting for one here
+    // would block forever: this only returns those already received.
+    pub fn try_next(&self) -> Option<CollectionEvent> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+pub struct Items {
+    collection: Collection,
+    items: Option<IntoIter<Item>>,
+    load_secrets: bool,
+}
+
+impl Items {
+    fn load(&self) -> Result<Vec<Item>, error::Error> {
+        if !self.collection.get_flags().contains(COLLECTION_LOAD_ITEMS) {
+            self.collection.load_items_sync()?;
+        }
+        let items = self.collection.get_items();
+        if self.load_secrets && !items.is_empty() {
+            Item::load_secrets_sync(&items)?;
+        }
+        Ok(items)
+    }
+}
+
+impl Iterator for Items {
+    type Item = Result<Item, error::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.items.is_none() {
+            match self.load() {
+                Ok(items) => self.items = Some(items.into_iter()),
+                Err(error) => {
+                    self.items = Some(vec![].into_iter());
+                    return Some(Err(error));
+                },
+            }
+        }
+        self.items.as_mut().and_then(Iterator::next).map(Ok)
+    }
+}
 
 impl Collection {
+    pub fn connect_item_events<F: Fn(&Collection, CollectionEvent) + 'static>(&self, callback: F) -> SignalHandler {
+        let callback = Rc::new(callback);
+        let f: Box<Fn(&Collection, &str, &Variant) + 'static> = Box::new(move |collection: &Collection, signal_name: &str, parameters: &Variant| {
+            if parameters.get_type_string() != "(o)" {
+                return;
+            }
+            let item_path = parameters.get_child_value(0).get_str().unwrap_or_default();
+            let loaded_item = collection.get_items().into_iter().find(|item| item.get_object_path() == item_path);
+            let event: fn(Item) -> CollectionEvent =
+                match signal_name {
+                    "ItemChanged" => ItemChanged,
+                    "ItemCreated" => ItemCreated,
+                    "ItemDeleted" => return callback(collection, ItemDeleted(item_path, loaded_item)),
+                    _ => return,
+                };
+            match loaded_item {
+                Some(item) => callback(collection, event(item)),
+                None => {
+                    let collection = collection.clone();
+                    let callback = callback.clone();
+                    let path = item_path.clone();
+                    Item::new_for_dbus_path(collection.get_service().as_ref(), &item_path, ITEM_NONE, move |result| {
+                        match result {
+                            Ok(item) => callback(&collection, event(item)),
+                            Err(error) => callback(&collection, LoadFailed(path.clone(), error)),
+                        }
+                    });
+                },
+            }
+        });
+        unsafe {
+            let trampoline: GCallback = transmute(collection_g_signal_trampoline as usize);
+            connect(self.to_glib_none().0 as *mut _, "g-signal", trampoline, f)
+        }
+    }
+
+    pub fn connect_label_notify<F: Fn(&Collection) + 'static>(&self, callback: F) -> SignalHandler {
+        unsafe {
+            connect_notify(self.to_glib_none().0 as *mut _, "label", Box::new(move |this| {
+                let collection: Collection = from_glib_none(this as *mut ffi::SecretCollection);
+                callback(&collection)
+            }))
+        }
+    }
+
     pub fn create<F: Fn(Result<Collection, error::Error>) + 'static>(label: &str, callback: F) {
+        Collection::create_full(None, label, None, COLLECTION_CREATE_NONE, callback);
+    }
+
+    pub fn create_full<F: Fn(Result<Collection, error::Error>) + 'static>(service: Option<&Service>, label: &str, alias: Option<&str>, flags: CollectionCreateFlags, callback: F) {
         let label = CString::new(label).unwrap();
         let trampoline: AsyncReadyCallback = unsafe { transmute(collection_create_trampoline as usize) };
         type BoxedFn = Box<Fn(Result<Collection, error::Error>) + 'static>;
         let f: Box<BoxedFn> = Box::new(Box::new(callback));
         let user_data: *mut c_void = Box::into_raw(f) as *mut _;
-        unsafe { ffi::secret_collection_create(null_mut(), label.as_ptr(), null_mut(), COLLECTION_CREATE_NONE.to_glib(), null_mut(), trampoline, user_data) };
+        unsafe { ffi::secret_collection_create(service.to_glib_none().0, label.as_ptr(), alias.to_glib_none().0, flags.to_glib(), null_mut(), trampoline, user_data) };
+    }
+
+    pub fn create_full_sync(service: Option<&Service>, label: &str, alias: Option<&str>, flags: CollectionCreateFlags) -> Result<Collection, error::Error> {
+        let label = CString::new(label).unwrap();
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_collection_create_sync(service.to_glib_none().0, label.as_ptr(), alias.to_glib_none().0, flags.to_glib(), null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(result) })
+        }
+    }
+
+    pub fn create_sync(label: &str) -> Result<Collection, error::Error> {
+        Collection::create_full_sync(None, label, None, COLLECTION_CREATE_NONE)
+    }
+
+    pub fn clear_attachment<F: Fn(Result<bool, PasswordError>) + 'static>(&self, schema: &Schema, attributes: &HashMap<String, String>, callback: F) {
+        let attributes = attachment_attributes(schema, attributes);
+        let collection = self.clone();
+        attachment::clear(move |attributes, callback| collection.search_chunks(attributes, callback), &attributes, Box::new(callback));
     }
 
     pub fn delete<F: Fn(Result<bool, error::Error>) + 'static>(&self, callback: F) {
@@ -61,36 +210,212 @@ impl Collection {
         unsafe { ffi::secret_collection_delete(self.to_glib_none().0, null_mut(), trampoline, user_data) };
     }
 
+    pub fn for_alias<F: Fn(Result<Option<Collection>, error::Error>) + 'static>(service: Option<&Service>, alias: &str, flags: CollectionFlags, callback: F) {
+        let alias = CString::new(alias).unwrap();
+        let trampoline: AsyncReadyCallback = unsafe { transmute(collection_for_alias_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<Option<Collection>, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_collection_for_alias(service.to_glib_none().0, alias.as_ptr(), flags.to_glib(), null_mut(), trampoline, user_data) };
+    }
+
+    pub fn for_alias_sync(service: Option<&Service>, alias: &str, flags: CollectionFlags) -> Result<Option<Collection>, error::Error> {
+        let alias = CString::new(alias).unwrap();
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_collection_for_alias_sync(service.to_glib_none().0, alias.as_ptr(), flags.to_glib(), null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(result) })
+        }
+    }
+
     pub fn item_create<F: Fn(Result<Item, error::Error>) + 'static>(&self, schema: &Schema, label: &str, password: &str, attributes: &HashMap<String, String>, callback: F) {
-        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, attributes) };
-        let trampoline: AsyncReadyCallback = unsafe { transmute(item_create_trampoline as usize) };
-        type BoxedFn = Box<Fn(Result<Item, error::Error>) + 'static>;
+        let value = Value::new(password.as_bytes(), "text/plain");
+        Item::create(self, Some(schema), attributes, label, &value, ITEM_CREATE_REPLACE, callback);
+    }
+
+    pub fn item_create_temporary<F: Fn(Result<TempItem, error::Error>) + 'static>(&self, schema: &Schema, label: &str, password: &str, attributes: &HashMap<String, String>, callback: F) {
+        let value = Value::new(password.as_bytes(), "text/plain");
+        // Never replace an existing item: the guard must only delete the item it created.
+        Item::create(self, Some(schema), attributes, label, &value, ITEM_CREATE_NONE, move |result| {
+            callback(result.map(TempItem::new))
+        });
+    }
+
+    pub fn get_object_path(&self) -> String {
+        unsafe { from_glib_none(gio_sys::g_dbus_proxy_get_object_path(self.to_glib_none().0 as *mut _)) }
+    }
+
+    pub fn item_events(&self) -> CollectionEvents {
+        let (sender, receiver) = channel();
+        let handler = self.connect_item_events(move |_, event| {
+            let _ = sender.send(event);
+        });
+        CollectionEvents {
+            _handler: handler,
+            receiver: receiver,
+        }
+    }
+
+    pub fn items(&self, load_secrets: bool) -> Items {
+        Items {
+            collection: self.clone(),
+            items: None,
+            load_secrets: load_secrets,
+        }
+    }
+
+    pub fn load_items<F: Fn(Result<bool, error::Error>) + 'static>(&self, callback: F) {
+        let trampoline: AsyncReadyCallback = unsafe { transmute(collection_load_items_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
         let f: Box<BoxedFn> = Box::new(Box::new(callback));
         let user_data: *mut c_void = Box::into_raw(f) as *mut _;
-        let label = CString::new(label).unwrap();
-        let password = CString::new(password).unwrap();
-        let content_type = CString::new("text/plain").unwrap();
-        let value = unsafe { ffi::secret_value_new(password.as_ptr(), -1, content_type.as_ptr()) };
-        unsafe {
-            ffi::secret_item_create(
-                self.to_glib_none().0, schema.to_glib_none().0, hash_table,
-                label.as_ptr(), value, ITEM_CREATE_REPLACE.to_glib(), ::std::ptr::null_mut(), trampoline, user_data,
-            );
+        unsafe { ffi::secret_collection_load_items(self.to_glib_none().0, null_mut(), trampoline, user_data) };
+    }
+
+    pub fn load_items_sync(&self) -> Result<bool, error::Error> {
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_collection_load_items_sync(self.to_glib_none().0, null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        }
+    }
+
+    pub fn lock<F: Fn(Result<bool, error::Error>) + 'static>(&self, callback: F) {
+        match self.service() {
+            Ok(service) => service.lock(&[SecretObject::Collection(self.clone())], move |report| callback(report.map(|report| !report.changed.is_empty()))),
+            Err(error) => callback(Err(error)),
+        }
+    }
+
+    pub fn lock_sync(&self) -> Result<bool, error::Error> {
+        let report = self.service()?.lock_sync(&[SecretObject::Collection(self.clone())])?;
+        Ok(!report.changed.is_empty())
+    }
+
+    pub fn lookup_attachment<F: Fn(Result<Vec<u8>, PasswordError>) + 'static>(&self, schema: &Schema, attributes: &HashMap<String, String>, callback: F) {
+        let attributes = attachment_attributes(schema, attributes);
+        let collection = self.clone();
+        attachment::lookup(move |attributes, callback| {
+            collection.search_items(None, attributes, SEARCH_ALL | SEARCH_UNLOCK, move |items| callback(items.map_err(Glib)))
+        }, &attributes, Box::new(callback));
+    }
+
+    pub fn new_for_dbus_path<F: Fn(Result<Collection, error::Error>) + 'static>(service: Option<&Service>, collection_path: &str, flags: CollectionFlags, callback: F) {
+        let collection_path = CString::new(collection_path).unwrap();
+        let trampoline: AsyncReadyCallback = unsafe { transmute(collection_new_for_dbus_path_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<Collection, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_collection_new_for_dbus_path(service.to_glib_none().0, collection_path.as_ptr(), flags.to_glib(), null_mut(), trampoline, user_data) };
+    }
+
+    pub fn new_for_dbus_path_sync(service: Option<&Service>, collection_path: &str, flags: CollectionFlags) -> Result<Collection, error::Error> {
+        let collection_path = CString::new(collection_path).unwrap();
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_collection_new_for_dbus_path_sync(service.to_glib_none().0, collection_path.as_ptr(), flags.to_glib(), null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_object_error(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(result) })
         }
     }
 
     pub fn search<F: Fn(Result<Vec<Item>, error::Error>) + 'static>(&self, schema: &Schema, attributes: &HashMap<String, String>, callback: F) {
-        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, attributes) };
-        let trampoline: AsyncReadyCallback = unsafe { transmute(collection_search_trampoline as usize) };
-        type BoxedFn = Box<Fn(Result<Vec<Item>, ::glib::error::Error>) + 'static>;
+        self.search_with_options(schema, attributes, &SearchOptions::new(), callback);
+    }
+
+    pub fn search_with_options<F: Fn(Result<Vec<Item>, error::Error>) + 'static>(&self, schema: &Schema, attributes: &HashMap<String, String>, options: &SearchOptions, callback: F) {
+        self.search_items(Some(schema), attributes, options.get_flags(), callback);
+    }
+
+    pub fn set_alias<F: Fn(Result<bool, error::Error>) + 'static>(&self, alias: &str, callback: F) {
+        match self.service() {
+            Ok(service) => service.set_alias(alias, Some(self), callback),
+            Err(error) => callback(Err(error)),
+        }
+    }
+
+    pub fn set_alias_sync(&self, alias: &str) -> Result<bool, error::Error> {
+        self.service()?.set_alias_sync(alias, Some(self))
+    }
+
+    pub fn set_label<F: Fn(Result<bool, error::Error>) + 'static>(&self, label: &str, callback: F) {
+        let label = CString::new(label).unwrap();
+        let trampoline: AsyncReadyCallback = unsafe { transmute(collection_set_label_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
         let f: Box<BoxedFn> = Box::new(Box::new(callback));
         let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_collection_set_label(self.to_glib_none().0, label.as_ptr(), null_mut(), trampoline, user_data) };
+    }
 
-        unsafe {
-            ffi::secret_collection_search(self.to_glib_none().0, schema.to_glib_none().0,
-                hash_table, (SEARCH_ALL | SEARCH_LOAD_SECRETS | SEARCH_UNLOCK).to_glib(), null_mut(), trampoline, user_data)
+    pub fn set_label_sync(&self, label: &str) -> Result<bool, error::Error> {
+        let label = CString::new(label).unwrap();
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_collection_set_label_sync(self.to_glib_none().0, label.as_ptr(), null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
         }
     }
+
+    pub fn store_attachment<F: Fn(Result<bool, PasswordError>) + 'static>(&self, schema: &Schema, label: &str, data: &[u8], attributes: &HashMap<String, String>, callback: F) {
+        let attributes = attachment_attributes(schema, attributes);
+        let collection = self.clone();
+        let search_collection = self.clone();
+        attachment::replace(move |chunk, callback| {
+            Item::create(&collection, None, &chunk.attributes, &chunk.label, &chunk.value, ITEM_CREATE_NONE, move |result| {
+                callback(result.map(|_| true))
+            })
+        }, move |attributes, callback| search_collection.search_chunks(attributes, callback), &attributes, label, data, Box::new(callback));
+    }
+
+    pub fn unlock<F: Fn(Result<bool, error::Error>) + 'static>(&self, callback: F) {
+        match self.service() {
+            Ok(service) => service.unlock(&[SecretObject::Collection(self.clone())], move |report| callback(report.map(|report| !report.changed.is_empty()))),
+            Err(error) => callback(Err(error)),
+        }
+    }
+
+    pub fn unlock_sync(&self) -> Result<bool, error::Error> {
+        let report = self.service()?.unlock_sync(&[SecretObject::Collection(self.clone())])?;
+        Ok(!report.changed.is_empty())
+    }
+
+    fn search_chunks(&self, attributes: &HashMap<String, String>, callback: Box<Fn(Result<Vec<Item>, PasswordError>)>) {
+        self.search_items(None, attributes, SEARCH_ALL, move |items| callback(items.map_err(Glib)));
+    }
+
+    fn search_items<F: Fn(Result<Vec<Item>, error::Error>) + 'static>(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>, flags: SearchFlags, callback: F) {
+        let collection = self.clone();
+        let schema = schema.cloned();
+        let attributes = attributes.clone();
+        // The search can load the secrets of the items it finds.
+        check_access(self.get_service().as_ref(), move |callback| {
+            let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, &attributes) };
+            let trampoline: AsyncReadyCallback = unsafe { transmute(collection_search_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<Vec<Item>, ::glib::error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+
+            unsafe {
+                ffi::secret_collection_search(collection.to_glib_none().0, schema.to_glib_none().0,
+                    hash_table, flags.to_glib(), null_mut(), trampoline, user_data)
+            }
+        }, callback);
+    }
+
+    fn service(&self) -> Result<Service, error::Error> {
+        self.get_service().ok_or_else(|| new_error(NoSuchObject, "The collection is not attached to a service"))
+    }
 }
 
 unsafe extern "C" fn collection_create_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
@@ -102,7 +427,7 @@ unsafe extern "C" fn collection_create_trampoline(_this: *mut gobject_ffi::GObje
             Err(from_glib_full(error))
         }
         else {
-            Ok(from_glib_none(result))
+            Ok(from_glib_full(result))
         };
     let f: &Box<Fn(Result<Collection, error::Error>) + 'static> = &*(f as *const _);
     f(value)
@@ -123,6 +448,60 @@ unsafe extern "C" fn collection_delete_trampoline(this: *mut gobject_ffi::GObjec
     f(value)
 }
 
+unsafe extern "C" fn collection_for_alias_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_collection_for_alias_finish(result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<Option<Collection>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn collection_g_signal_trampoline(this: *mut gio_sys::GDBusProxy, _sender_name: *mut c_char, signal_name: *mut c_char, parameters: *mut glib_ffi::GVariant, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let collection: Collection = from_glib_none(this as *mut ffi::SecretCollection);
+    let signal_name: String = from_glib_none(signal_name as *const c_char);
+    let parameters: Variant = from_glib_none(parameters);
+    let f: &Box<Fn(&Collection, &str, &Variant) + 'static> = &*(f as *const _);
+    f(&collection, &signal_name, &parameters)
+}
+
+unsafe extern "C" fn collection_load_items_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_collection_load_items_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn collection_new_for_dbus_path_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_collection_new_for_dbus_path_finish(result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_object_error(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<Collection, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
 unsafe extern "C" fn collection_search_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
     callback_guard!();
     let mut error = ::std::ptr::null_mut();
@@ -147,17 +526,23 @@ unsafe extern "C" fn collection_search_trampoline(this: *mut gobject_ffi::GObjec
     f(value)
 }
 
-unsafe extern "C" fn item_create_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+unsafe extern "C" fn collection_set_label_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
     callback_guard!();
     let mut error = null_mut();
-    let result = ffi::secret_item_create_finish(result, &mut error);
+    let result = ffi::secret_collection_set_label_finish(this as *mut _, result, &mut error);
     let value =
         if !error.is_null() {
             Err(from_glib_full(error))
         }
         else {
-            Ok(from_glib_none(result))
+            Ok(FromGlib::from_glib(result))
         };
-    let f: &Box<Fn(Result<Item, error::Error>) + 'static> = &*(f as *const _);
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
     f(value)
 }
+
+// Unlike Collection::search_with_options, the schema is optional so that the attachment chunks,
+// stored without a schema, can be found.
+pub fn search_collection<F: Fn(Result<Vec<Item>, error::Error>) + 'static>(collection: &Collection, schema: Option<&Schema>, attributes: &HashMap<String, String>, options: &SearchOptions, callback: F) {
+    collection.search_items(schema, attributes, options.get_flags(), callback);
+}
@@ -0,0 +1,82 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use glib::error;
+
+use Collection;
+use COLLECTION_LOAD_ITEMS;
+
+// The Secret Service API cannot list the aliases, so only these well-known ones are reported.
+const ALIASES: &'static [&'static str] = &["default", "login", "session"];
+
+#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[derive(Clone, Debug)]
+pub struct CollectionInfo {
+    pub aliases: Vec<String>,
+    pub created: SystemTime,
+    pub item_count: usize,
+    pub item_counts: HashMap<String, usize>,
+    pub label: Option<String>,
+    pub locked: bool,
+    pub modified: SystemTime,
+    pub object_path: String,
+}
+
+impl CollectionInfo {
+    // This blocks on D-Bus calls to read the aliases and to load the items when they are not
+    // loaded yet.
+    pub fn new(collection: &Collection) -> Result<Self, error::Error> {
+        let object_path = collection.get_object_path();
+        let mut aliases = vec![];
+        if let Some(service) = collection.get_service() {
+            for alias in ALIASES {
+                if service.read_alias_dbus_path_sync(alias)?.as_ref() == Some(&object_path) {
+                    aliases.push(alias.to_string());
+                }
+            }
+        }
+        if !collection.get_flags().contains(COLLECTION_LOAD_ITEMS) {
+            collection.load_items_sync()?;
+        }
+        let items = collection.get_items();
+        let mut item_counts = HashMap::new();
+        for item in &items {
+            *item_counts.entry(item.get_schema_name().unwrap_or_default()).or_insert(0) += 1;
+        }
+        Ok(CollectionInfo {
+            aliases: aliases,
+            created: to_system_time(collection.get_created()),
+            item_count: items.len(),
+            item_counts: item_counts,
+            label: collection.get_label(),
+            locked: collection.get_locked(),
+            modified: to_system_time(collection.get_modified()),
+            object_path: object_path,
+        })
+    }
+}
+
+fn to_system_time(timestamp: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(timestamp)
+}
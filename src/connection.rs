@@ -0,0 +1,149 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::ffi::CString;
+use std::mem::transmute;
+use std::ptr::{null, null_mut};
+
+use ffi;
+use gio_sys;
+use glib::error;
+use glib::translate::{ToGlib, ToGlibPtr, from_glib_full, from_glib_none};
+use glib_ffi;
+use gobject_ffi;
+use libc::{c_char, c_void};
+
+use AsyncReadyCallback;
+use Service;
+use ServiceFlags;
+
+const SERVICE_INTERFACE: &'static [u8] = b"org.freedesktop.Secret.Service\0";
+const SERVICE_NAME: &'static [u8] = b"org.freedesktop.secrets\0";
+const SERVICE_PATH: &'static [u8] = b"/org/freedesktop/secrets\0";
+
+glib_wrapper! {
+    pub struct DBusConnection(Object<gio_sys::GDBusConnection>);
+
+    match fn {
+        get_type => || gio_sys::g_dbus_connection_get_type(),
+    }
+}
+
+impl DBusConnection {
+    pub fn new_for_address_sync(address: &str) -> Result<DBusConnection, error::Error> {
+        DBusConnection::connect_sync(address,
+            gio_sys::G_DBUS_CONNECTION_FLAGS_AUTHENTICATION_CLIENT | gio_sys::G_DBUS_CONNECTION_FLAGS_MESSAGE_BUS_CONNECTION)
+    }
+
+    pub fn new_for_peer_address_sync(address: &str) -> Result<DBusConnection, error::Error> {
+        DBusConnection::connect_sync(address, gio_sys::G_DBUS_CONNECTION_FLAGS_AUTHENTICATION_CLIENT)
+    }
+
+    pub fn get_unique_name(&self) -> Option<String> {
+        unsafe { from_glib_none(gio_sys::g_dbus_connection_get_unique_name(self.to_glib_none().0)) }
+    }
+
+    fn connect_sync(address: &str, flags: gio_sys::GDBusConnectionFlags) -> Result<DBusConnection, error::Error> {
+        let address = CString::new(address).unwrap();
+        let mut error = null_mut();
+        let connection = unsafe {
+            gio_sys::g_dbus_connection_new_for_address_sync(address.as_ptr(), flags, null_mut(), null_mut(), &mut error)
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(connection) })
+        }
+    }
+}
+
+impl Service {
+    pub fn new_for_connection<F: Fn(Result<Service, error::Error>) + 'static>(connection: &DBusConnection, flags: ServiceFlags, callback: F) {
+        new_service(connection, service_name(connection), flags, callback);
+    }
+
+    pub fn new_for_connection_sync(connection: &DBusConnection, flags: ServiceFlags) -> Result<Service, error::Error> {
+        let mut error = null_mut();
+        let service = unsafe {
+            gio_sys::g_initable_new(ffi::secret_service_get_type(), null_mut(), &mut error,
+                b"flags\0".as_ptr() as *const c_char, flags.to_glib().bits(),
+                b"g-connection\0".as_ptr() as *const c_char, connection.to_glib_none().0,
+                b"g-name\0".as_ptr() as *const c_char, service_name(connection),
+                b"g-object-path\0".as_ptr() as *const c_char, SERVICE_PATH.as_ptr() as *const c_char,
+                b"g-interface-name\0".as_ptr() as *const c_char, SERVICE_INTERFACE.as_ptr() as *const c_char,
+                null::<c_char>())
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(service as *mut ffi::SecretService) })
+        }
+    }
+}
+
+// Unlike Service::new_for_connection, the service is reached through the given bus name.
+pub fn new_for_name<F: Fn(Result<Service, error::Error>) + 'static>(connection: &DBusConnection, name: &str, flags: ServiceFlags, callback: F) {
+    let name = CString::new(name).unwrap();
+    new_service(connection, name.as_ptr(), flags, callback);
+}
+
+fn new_service<F: Fn(Result<Service, error::Error>) + 'static>(connection: &DBusConnection, name: *const c_char, flags: ServiceFlags, callback: F) {
+    let trampoline: AsyncReadyCallback = unsafe { transmute(service_new_for_connection_trampoline as usize) };
+    type BoxedFn = Box<Fn(Result<Service, error::Error>) + 'static>;
+    let f: Box<BoxedFn> = Box::new(Box::new(callback));
+    let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+    unsafe {
+        gio_sys::g_async_initable_new_async(ffi::secret_service_get_type(), glib_ffi::G_PRIORITY_DEFAULT, null_mut(), trampoline, user_data,
+            b"flags\0".as_ptr() as *const c_char, flags.to_glib().bits(),
+            b"g-connection\0".as_ptr() as *const c_char, connection.to_glib_none().0,
+            b"g-name\0".as_ptr() as *const c_char, name,
+            b"g-object-path\0".as_ptr() as *const c_char, SERVICE_PATH.as_ptr() as *const c_char,
+            b"g-interface-name\0".as_ptr() as *const c_char, SERVICE_INTERFACE.as_ptr() as *const c_char,
+            null::<c_char>());
+    }
+}
+
+// A peer-to-peer connection has no bus, so the proxy must not be bound to a bus name.
+fn service_name(connection: &DBusConnection) -> *const c_char {
+    if connection.get_unique_name().is_some() {
+        SERVICE_NAME.as_ptr() as *const c_char
+    }
+    else {
+        null()
+    }
+}
+
+unsafe extern "C" fn service_new_for_connection_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let service = gio_sys::g_async_initable_new_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_full(service as *mut ffi::SecretService))
+        };
+    let f: &Box<Fn(Result<Service, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
@@ -0,0 +1,93 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::ffi::CString;
+
+use ffi;
+use gio_sys;
+use glib::error;
+use glib::translate::{ToGlib, ToGlibPtr, from_glib_full, from_glib_none};
+use glib_ffi;
+
+use Error::{self, NoSuchObject};
+
+pub fn is_dbus_error(error: &error::Error, code: gio_sys::GDBusError) -> bool {
+    let error = error.to_glib_none().0;
+    unsafe { (*error).domain == gio_sys::g_dbus_error_quark() && (*error).code == code as i32 }
+}
+
+pub fn new_error(code: Error, message: &str) -> error::Error {
+    let message = CString::new(message).unwrap();
+    unsafe {
+        from_glib_full(glib_ffi::g_error_new_literal(ffi::secret_error_get_quark(), code.to_glib() as i32, message.as_ptr()))
+    }
+}
+
+// libsecret reports a proxy to a missing collection or item as an unknown method error naming
+// the path, and the service itself can report an unknown object.
+pub unsafe fn from_glib_object_error(error: *mut glib_ffi::GError) -> error::Error {
+    let message: String = from_glib_none((*error).message);
+    if (*error).domain == gio_sys::g_dbus_error_quark() && is_missing_object((*error).code, &message) {
+        glib_ffi::g_error_free(error);
+        new_error(NoSuchObject, &message)
+    }
+    else {
+        from_glib_full(error)
+    }
+}
+
+pub fn with_attempts(error: &error::Error, attempts: u32) -> error::Error {
+    let error = error.to_glib_none().0;
+    unsafe {
+        let message: String = from_glib_none((*error).message);
+        let message = CString::new(format!("{} (after {} attempts)", message, attempts)).unwrap();
+        from_glib_full(glib_ffi::g_error_new_literal((*error).domain, (*error).code, message.as_ptr()))
+    }
+}
+
+fn is_missing_object(code: i32, message: &str) -> bool {
+    if code == gio_sys::G_DBUS_ERROR_UNKNOWN_OBJECT as i32 {
+        return true;
+    }
+    code == gio_sys::G_DBUS_ERROR_UNKNOWN_METHOD as i32 &&
+        (message.starts_with("No such secret collection at path: ") || message.starts_with("No such secret item at path: "))
+}
+
+#[cfg(test)]
+mod tests {
+    use gio_sys;
+
+    use super::is_missing_object;
+
+    #[test]
+    fn missing_objects_are_detected() {
+        let unknown_method = gio_sys::G_DBUS_ERROR_UNKNOWN_METHOD as i32;
+        assert!(is_missing_object(gio_sys::G_DBUS_ERROR_UNKNOWN_OBJECT as i32, ""));
+        assert!(is_missing_object(unknown_method, "No such secret collection at path: /org/freedesktop/secrets/collection/old"));
+        assert!(is_missing_object(unknown_method, "No such secret item at path: /org/freedesktop/secrets/collection/login/1"));
+    }
+
+    #[test]
+    fn other_errors_are_kept() {
+        assert!(!is_missing_object(gio_sys::G_DBUS_ERROR_UNKNOWN_METHOD as i32, "No such method 'Unlock'"));
+        assert!(!is_missing_object(gio_sys::G_DBUS_ERROR_ACCESS_DENIED as i32, "No such secret item at path: /org/freedesktop/secrets/collection/login/1"));
+    }
+}
@@ -20,22 +20,98 @@
  */
 
 use std::collections::HashMap;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::mem::{transmute, uninitialized};
 use std::ptr::null_mut;
+use std::rc::Rc;
 
 use ffi;
 use gio_sys;
 use glib::error;
-use glib::translate::{ToGlibPtr, from_glib_full};
+use glib::translate::{FromGlib, ToGlib, ToGlibPtr, from_glib_full, from_glib_none};
 use glib_ffi::{self, GHashTableIter, g_hash_table_iter_init, g_hash_table_iter_next};
 use gobject_ffi;
 use libc::c_void;
 
+use access::{check_access, check_access_sync};
 use AsyncReadyCallback;
+use Collection;
+use PasswordError::{self, Conflict, Glib};
+use Error::{IsLocked, Protocol};
+use error::new_error;
 use Item;
+use ItemCreateFlags;
+use ItemFlags;
+use ITEM_CREATE_NONE;
+use ITEM_LOAD_SECRET;
+use Schema;
+use Service;
+use Value;
+use error::from_glib_object_error;
+use signal::{SignalHandler, connect_notify};
+use to_glib_string_hash_map;
 
 impl Item {
+    pub fn connect_attributes_notify<F: Fn(&Item) + 'static>(&self, callback: F) -> SignalHandler {
+        self.connect_property_notify("attributes", callback)
+    }
+
+    pub fn connect_label_notify<F: Fn(&Item) + 'static>(&self, callback: F) -> SignalHandler {
+        self.connect_property_notify("label", callback)
+    }
+
+    pub fn connect_locked_notify<F: Fn(&Item) + 'static>(&self, callback: F) -> SignalHandler {
+        self.connect_property_notify("locked", callback)
+    }
+
+    pub fn connect_modified_notify<F: Fn(&Item) + 'static>(&self, callback: F) -> SignalHandler {
+        self.connect_property_notify("modified", callback)
+    }
+
+    pub fn copy_to<F: Fn(Result<Item, error::Error>) + 'static>(&self, collection: &Collection, callback: F) {
+        let item = self.clone();
+        let collection = collection.clone();
+        let callback = Rc::new(callback);
+        self.load_secret(move |result| {
+            if let Err(error) = result {
+                return callback(Err(error));
+            }
+            let value =
+                match item.get_secret() {
+                    Some(value) => value,
+                    None => return callback(Err(new_error(IsLocked, "Cannot copy an item whose secret is locked"))),
+                };
+            // The attributes include xdg:schema, so the schema is kept without passing it.
+            let label = item.get_label().unwrap_or_default();
+            let callback = callback.clone();
+            Item::create(&collection, None, &item.get_attributes(), &label, &value, ITEM_CREATE_NONE, move |result| {
+                callback(result)
+            });
+        });
+    }
+
+    pub fn create<F: Fn(Result<Item, error::Error>) + 'static>(collection: &Collection, schema: Option<&Schema>, attributes: &HashMap<String, String>, label: &str, value: &Value, flags: ItemCreateFlags, callback: F) {
+        let service = collection.get_service();
+        let collection = collection.clone();
+        let schema = schema.cloned();
+        let attributes = attributes.clone();
+        let label = CString::new(label).unwrap();
+        let value = value.clone();
+        check_access(service.as_ref(), move |callback| {
+            let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, &attributes) };
+            let trampoline: AsyncReadyCallback = unsafe { transmute(item_create_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<Item, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe {
+                ffi::secret_item_create(
+                    collection.to_glib_none().0, schema.to_glib_none().0, hash_table,
+                    label.as_ptr(), value.to_glib_none().0, flags.to_glib(), null_mut(), trampoline, user_data,
+                );
+            }
+        }, callback);
+    }
+
     pub fn delete<F: Fn(Result<bool, error::Error>) + 'static>(&self, callback: F) {
         let trampoline: AsyncReadyCallback = unsafe { transmute(item_delete_trampoline as usize) };
         type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
@@ -48,6 +124,17 @@ impl Item {
         }
     }
 
+    pub fn delete_sync(&self) -> Result<bool, error::Error> {
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_item_delete_sync(self.to_glib_none().0, null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        }
+    }
+
     pub fn get_attributes(&self) -> HashMap<String, String> {
         let mut attributes = HashMap::new();
         let hash_table = unsafe { ::ffi::secret_item_get_attributes(self.to_glib_none().0) };
@@ -62,6 +149,184 @@ impl Item {
         }
         attributes
     }
+
+    pub fn get_object_path(&self) -> String {
+        unsafe { from_glib_none(gio_sys::g_dbus_proxy_get_object_path(self.to_glib_none().0 as *mut _)) }
+    }
+
+    pub fn load_secret<F: Fn(Result<bool, error::Error>) + 'static>(&self, callback: F) {
+        let item = self.clone();
+        check_access(self.get_service().as_ref(), move |callback| {
+            let trampoline: AsyncReadyCallback = unsafe { transmute(item_load_secret_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe { ffi::secret_item_load_secret(item.to_glib_none().0, null_mut(), trampoline, user_data) };
+        }, callback);
+    }
+
+    // libsecret loads the secrets of all the items through the service of the first one.
+    pub fn load_secrets<F: Fn(Result<bool, error::Error>) + 'static>(items: &[Item], callback: F) {
+        let service = items.first().and_then(Item::get_service);
+        let items = items.to_vec();
+        check_access(service.as_ref(), move |callback| {
+            let trampoline: AsyncReadyCallback = unsafe { transmute(item_load_secrets_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe {
+                let list = to_glib_list(&items);
+                ffi::secret_item_load_secrets(list, null_mut(), trampoline, user_data);
+                glib_ffi::g_list_free(list);
+            }
+        }, callback);
+    }
+
+    pub fn load_secrets_sync(items: &[Item]) -> Result<bool, error::Error> {
+        check_access_sync(items.first().and_then(Item::get_service).as_ref())?;
+        let mut error = null_mut();
+        let result = unsafe {
+            let list = to_glib_list(items);
+            let result = ffi::secret_item_load_secrets_sync(list, null_mut(), &mut error);
+            glib_ffi::g_list_free(list);
+            result
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        }
+    }
+
+    pub fn move_to<F: Fn(Result<Item, error::Error>) + 'static>(&self, collection: &Collection, callback: F) {
+        let item = self.clone();
+        let callback = Rc::new(callback);
+        self.copy_to(collection, move |result| {
+            match result {
+                Ok(copy) => {
+                    let callback = callback.clone();
+                    let paths = (item.get_object_path(), copy.get_object_path());
+                    item.delete(move |result| {
+                        match result {
+                            Ok(_) => callback(Ok(copy.clone())),
+                            Err(error) => {
+                                // Remove the copy so that a failed move leaves both collections unchanged.
+                                let callback = callback.clone();
+                                let paths = paths.clone();
+                                copy.delete(move |result| {
+                                    match result {
+                                        Ok(_) => callback(Err(error.clone())),
+                                        Err(_) => callback(Err(new_error(Protocol, &format!(
+                                            "The move failed and so did its rollback: the item exists both at {} and at {}",
+                                            paths.0, paths.1)))),
+                                    }
+                                });
+                            },
+                        }
+                    });
+                },
+                Err(error) => callback(Err(error)),
+            }
+        });
+    }
+
+    pub fn new_for_dbus_path<F: Fn(Result<Item, error::Error>) + 'static>(service: Option<&Service>, item_path: &str, flags: ItemFlags, callback: F) {
+        if !flags.contains(ITEM_LOAD_SECRET) {
+            return Item::new_proxy_for_dbus_path(service, item_path, flags, callback);
+        }
+        // The secret is fetched through load_secret so that the access checks of the service run.
+        let mut flags = flags;
+        flags.remove(ITEM_LOAD_SECRET);
+        let callback = Rc::new(callback);
+        Item::new_proxy_for_dbus_path(service, item_path, flags, move |item| {
+            match item {
+                Ok(item) => {
+                    let callback = callback.clone();
+                    let loaded_item = item.clone();
+                    item.load_secret(move |result| callback(result.map(|_| loaded_item.clone())));
+                },
+                Err(error) => callback(Err(error)),
+            }
+        });
+    }
+
+    // The comparison and the write are separate D-Bus calls, so another writer can still
+    // slip in between them: this narrows the race but is not an atomic compare-and-swap.
+    pub fn replace_if_unchanged<F: Fn(Result<bool, PasswordError>) + 'static>(&self, modified: u64, secret: Option<&[u8]>, value: &Value, callback: F) {
+        let item = self.clone();
+        let secret = secret.map(|secret| secret.to_vec());
+        let value = value.clone();
+        let callback = Rc::new(callback);
+        // A new proxy fetches the current properties and secret instead of the cached ones.
+        Item::new_for_dbus_path(self.get_service().as_ref(), &self.get_object_path(), ITEM_LOAD_SECRET, move |current| {
+            let current =
+                match current {
+                    Ok(current) => current,
+                    Err(error) => return callback(Err(Glib(error))),
+                };
+            if current.get_locked() {
+                return callback(Err(Glib(new_error(IsLocked, "Cannot compare the secret of a locked item"))));
+            }
+            let same_secret =
+                match secret {
+                    Some(ref secret) => current.get_secret().map(|value| value.get()).as_ref() == Some(secret),
+                    None => true,
+                };
+            if current.get_modified() != modified || !same_secret {
+                return callback(Err(Conflict));
+            }
+            let callback = callback.clone();
+            item.set_secret(&value, move |result| {
+                callback(result.map_err(Glib))
+            });
+        });
+    }
+
+    pub fn set_secret<F: Fn(Result<bool, error::Error>) + 'static>(&self, value: &Value, callback: F) {
+        let item = self.clone();
+        let value = value.clone();
+        check_access(self.get_service().as_ref(), move |callback| {
+            let trampoline: AsyncReadyCallback = unsafe { transmute(item_set_secret_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe { ffi::secret_item_set_secret(item.to_glib_none().0, value.to_glib_none().0, null_mut(), trampoline, user_data) };
+        }, callback);
+    }
+
+    fn connect_property_notify<F: Fn(&Item) + 'static>(&self, property: &str, callback: F) -> SignalHandler {
+        unsafe {
+            connect_notify(self.to_glib_none().0 as *mut _, property, Box::new(move |this| {
+                let item: Item = from_glib_none(this as *mut ffi::SecretItem);
+                callback(&item)
+            }))
+        }
+    }
+
+    fn new_proxy_for_dbus_path<F: Fn(Result<Item, error::Error>) + 'static>(service: Option<&Service>, item_path: &str, flags: ItemFlags, callback: F) {
+        let item_path = CString::new(item_path).unwrap();
+        let trampoline: AsyncReadyCallback = unsafe { transmute(item_new_for_dbus_path_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<Item, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_item_new_for_dbus_path(service.to_glib_none().0, item_path.as_ptr(), flags.to_glib(), null_mut(), trampoline, user_data) };
+    }
+}
+
+unsafe extern "C" fn item_create_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_item_create_finish(result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<Item, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
 }
 
 unsafe extern "C" fn item_delete_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
@@ -78,3 +343,88 @@ unsafe extern "C" fn item_delete_trampoline(this: *mut gobject_ffi::GObject, res
     let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
     f(value)
 }
+
+unsafe extern "C" fn item_load_secret_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_item_load_secret_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn item_load_secrets_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_item_load_secrets_finish(result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn item_new_for_dbus_path_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_item_new_for_dbus_path_finish(result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_object_error(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<Item, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn item_set_secret_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_item_set_secret_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+pub fn delete_items<F: Fn(Result<bool, error::Error>) + 'static>(items: Vec<Item>, callback: F) {
+    delete_items_from(Rc::new(items), 0, false, Rc::new(callback));
+}
+
+fn delete_items_from(items: Rc<Vec<Item>>, index: usize, deleted: bool, callback: Rc<Fn(Result<bool, error::Error>)>) {
+    if index == items.len() {
+        return callback(Ok(deleted));
+    }
+    let next_items = items.clone();
+    items[index].delete(move |result| {
+        match result {
+            Ok(result) => delete_items_from(next_items.clone(), index + 1, deleted || result, callback.clone()),
+            Err(error) => callback(Err(error)),
+        }
+    });
+}
+
+unsafe fn to_glib_list(items: &[Item]) -> *mut glib_ffi::GList {
+    let mut list = null_mut();
+    for item in items {
+        list = glib_ffi::g_list_append(list, item.to_glib_none().0 as *mut _);
+    }
+    list
+}
@@ -28,6 +28,9 @@ extern crate glib_sys as glib_ffi;
 extern crate gobject_sys as gobject_ffi;
 extern crate gtk;
 extern crate libc;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
 
 extern crate secret_sys as ffi;
 
@@ -58,13 +61,39 @@ macro_rules! callback_guard {
 #[macro_use]
 mod macros;
 mod auto;
+mod access;
+mod attachment;
 mod collection;
+mod collection_info;
+mod connection;
+mod error;
 mod item;
+mod lock;
+mod owner;
 mod password;
+mod retry;
 mod schema;
+mod search;
 mod service;
+mod session;
+mod signal;
+mod temp_item;
+mod value;
+mod variant;
+mod watch;
 
 pub use auto::*;
+pub use collection::{CollectionEvent, CollectionEvents, Items};
+pub use collection_info::CollectionInfo;
+pub use connection::DBusConnection;
+pub use lock::{LockReport, SecretObject};
 pub use password::*;
+pub use retry::{RetryPolicy, RetryableError};
+pub use search::SearchOptions;
+pub use session::{ENCRYPTED_SESSION_ALGORITHMS, SessionPolicy};
+pub use signal::SignalHandler;
+pub use temp_item::TempItem;
+pub use variant::Variant;
+pub use watch::ServiceWatch;
 
 pub type AsyncReadyCallback = Option<unsafe extern "C" fn(*mut gobject_ffi::GObject, *mut gio_sys::GAsyncResult, *mut libc::c_void)>;
@@ -0,0 +1,199 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::mem::transmute;
+use std::ptr::null_mut;
+use std::rc::Rc;
+
+use gio_sys;
+use glib::error;
+use glib::translate::{ToGlibPtr, from_glib_full};
+use glib_ffi::{self, GList, g_list_append};
+use gobject_ffi;
+use libc::{c_char, c_void};
+
+use AsyncReadyCallback;
+use Collection;
+use Item;
+
+#[derive(Clone)]
+pub enum SecretObject {
+    Collection(Collection),
+    Item(Item),
+}
+
+impl SecretObject {
+    pub fn get_object_path(&self) -> String {
+        match *self {
+            SecretObject::Collection(ref collection) => collection.get_object_path(),
+            SecretObject::Item(ref item) => item.get_object_path(),
+        }
+    }
+
+    fn get_proxy(&self) -> *mut gio_sys::GDBusProxy {
+        match *self {
+            SecretObject::Collection(ref collection) => collection.to_glib_none().0 as *mut _,
+            SecretObject::Item(ref item) => item.to_glib_none().0 as *mut _,
+        }
+    }
+}
+
+impl From<Collection> for SecretObject {
+    fn from(collection: Collection) -> Self {
+        SecretObject::Collection(collection)
+    }
+}
+
+impl From<Item> for SecretObject {
+    fn from(item: Item) -> Self {
+        SecretObject::Item(item)
+    }
+}
+
+#[derive(Clone)]
+pub struct LockReport {
+    pub changed: Vec<SecretObject>,
+    pub unchanged: Vec<SecretObject>,
+}
+
+impl LockReport {
+    pub fn new(objects: Vec<SecretObject>, locked_before: &[bool], locked_after: &[bool]) -> Self {
+        let (changed, unchanged) = partition_changed(objects, locked_before, locked_after);
+        LockReport {
+            changed: changed,
+            unchanged: unchanged,
+        }
+    }
+}
+
+// The cached Locked property is only updated when the service emits a signal, so the state
+// is read from the service instead.
+pub fn read_locked<F: Fn(Result<Vec<bool>, error::Error>) + 'static>(objects: Vec<SecretObject>, callback: F) {
+    read_locked_from(Rc::new(objects), vec![], Rc::new(callback));
+}
+
+pub fn read_locked_sync(objects: &[SecretObject]) -> Result<Vec<bool>, error::Error> {
+    let mut states = vec![];
+    for object in objects {
+        let mut error = null_mut();
+        let reply = unsafe {
+            gio_sys::g_dbus_proxy_call_sync(object.get_proxy(), b"org.freedesktop.DBus.Properties.Get\0".as_ptr() as *const c_char,
+                locked_parameters(object.get_proxy()), gio_sys::G_DBUS_CALL_FLAGS_NONE, -1, null_mut(), &mut error)
+        };
+        if !error.is_null() {
+            return Err(unsafe { from_glib_full(error) });
+        }
+        states.push(unsafe { locked_from_reply(reply) });
+    }
+    Ok(states)
+}
+
+pub unsafe fn to_glib_object_list(objects: &[SecretObject]) -> *mut GList {
+    let mut list = null_mut();
+    for object in objects {
+        list = g_list_append(list, object.get_proxy() as *mut _);
+    }
+    list
+}
+
+unsafe fn locked_from_reply(reply: *mut glib_ffi::GVariant) -> bool {
+    let child = glib_ffi::g_variant_get_child_value(reply, 0);
+    let value = glib_ffi::g_variant_get_variant(child);
+    let locked = glib_ffi::g_variant_get_boolean(value) != 0;
+    glib_ffi::g_variant_unref(value);
+    glib_ffi::g_variant_unref(child);
+    glib_ffi::g_variant_unref(reply);
+    locked
+}
+
+unsafe fn locked_parameters(proxy: *mut gio_sys::GDBusProxy) -> *mut glib_ffi::GVariant {
+    let mut children = [
+        glib_ffi::g_variant_new_string(gio_sys::g_dbus_proxy_get_interface_name(proxy)),
+        glib_ffi::g_variant_new_string(b"Locked\0".as_ptr() as *const c_char),
+    ];
+    glib_ffi::g_variant_new_tuple(children.as_mut_ptr(), children.len())
+}
+
+fn partition_changed<T>(objects: Vec<T>, before: &[bool], after: &[bool]) -> (Vec<T>, Vec<T>) {
+    let mut changed = vec![];
+    let mut unchanged = vec![];
+    for ((object, before), after) in objects.into_iter().zip(before).zip(after) {
+        if before != after {
+            changed.push(object);
+        }
+        else {
+            unchanged.push(object);
+        }
+    }
+    (changed, unchanged)
+}
+
+fn read_locked_from(objects: Rc<Vec<SecretObject>>, states: Vec<bool>, callback: Rc<Fn(Result<Vec<bool>, error::Error>)>) {
+    let object =
+        match objects.get(states.len()) {
+            Some(object) => object.clone(),
+            None => return callback(Ok(states)),
+        };
+    let trampoline: AsyncReadyCallback = unsafe { transmute(secret_object_read_locked_trampoline as usize) };
+    type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
+    let f: Box<BoxedFn> = Box::new(Box::new(move |locked: Result<bool, error::Error>| {
+        match locked {
+            Ok(locked) => {
+                let mut states = states.clone();
+                states.push(locked);
+                read_locked_from(objects.clone(), states, callback.clone());
+            },
+            Err(error) => callback(Err(error)),
+        }
+    }));
+    let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+    unsafe {
+        gio_sys::g_dbus_proxy_call(object.get_proxy(), b"org.freedesktop.DBus.Properties.Get\0".as_ptr() as *const c_char,
+            locked_parameters(object.get_proxy()), gio_sys::G_DBUS_CALL_FLAGS_NONE, -1, null_mut(), trampoline, user_data);
+    }
+}
+
+unsafe extern "C" fn secret_object_read_locked_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let reply = gio_sys::g_dbus_proxy_call_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(locked_from_reply(reply))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::partition_changed;
+
+    #[test]
+    fn objects_are_partitioned_by_state_change() {
+        let (changed, unchanged) = partition_changed(vec!["first", "second", "third"], &[true, false, true], &[false, false, true]);
+        assert_eq!(changed, vec!["first"]);
+        assert_eq!(unchanged, vec!["second", "third"]);
+    }
+}
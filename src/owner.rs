@@ -0,0 +1,169 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::ffi::CString;
+use std::fs;
+use std::mem::transmute;
+use std::path::PathBuf;
+use std::ptr::null_mut;
+
+use gio_sys;
+use glib::error;
+use glib::translate::{ToGlibPtr, from_glib_full};
+use glib_ffi;
+use gobject_ffi;
+use libc::{c_char, c_void};
+
+use AsyncReadyCallback;
+use PasswordError::{self, Glib, UntrustedOwner};
+use Service;
+
+const ALLOWED_OWNERS_KEY: &'static [u8] = b"secret-rs-allowed-owners\0";
+
+impl Service {
+    pub fn get_allowed_owners(&self) -> Option<Vec<PathBuf>> {
+        unsafe {
+            let owners = gobject_ffi::g_object_get_data(self.to_glib_none().0 as *mut _, ALLOWED_OWNERS_KEY.as_ptr() as *const c_char);
+            if owners.is_null() {
+                None
+            }
+            else {
+                Some((*(owners as *const Vec<PathBuf>)).clone())
+            }
+        }
+    }
+
+    pub fn get_owner_executable<F: Fn(Result<Option<PathBuf>, error::Error>) + 'static>(&self, callback: F) {
+        let owner: Option<String> = unsafe { from_glib_full(gio_sys::g_dbus_proxy_get_name_owner(self.to_glib_none().0 as *mut _)) };
+        let owner =
+            match owner {
+                Some(owner) => owner,
+                None => return callback(Ok(None)),
+            };
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_get_owner_executable_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<Option<PathBuf>, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe {
+            gio_sys::g_dbus_connection_call(self.get_connection(), b"org.freedesktop.DBus\0".as_ptr() as *const c_char,
+                b"/org/freedesktop/DBus\0".as_ptr() as *const c_char, b"org.freedesktop.DBus\0".as_ptr() as *const c_char,
+                b"GetConnectionUnixProcessID\0".as_ptr() as *const c_char, process_id_parameters(&owner), null_mut(),
+                gio_sys::G_DBUS_CALL_FLAGS_NONE, -1, null_mut(), trampoline, user_data);
+        }
+    }
+
+    pub fn get_owner_executable_sync(&self) -> Result<Option<PathBuf>, error::Error> {
+        let owner: Option<String> = unsafe { from_glib_full(gio_sys::g_dbus_proxy_get_name_owner(self.to_glib_none().0 as *mut _)) };
+        let owner =
+            match owner {
+                Some(owner) => owner,
+                None => return Ok(None),
+            };
+        let mut error = null_mut();
+        let reply = unsafe {
+            gio_sys::g_dbus_connection_call_sync(self.get_connection(), b"org.freedesktop.DBus\0".as_ptr() as *const c_char,
+                b"/org/freedesktop/DBus\0".as_ptr() as *const c_char, b"org.freedesktop.DBus\0".as_ptr() as *const c_char,
+                b"GetConnectionUnixProcessID\0".as_ptr() as *const c_char, process_id_parameters(&owner), null_mut(),
+                gio_sys::G_DBUS_CALL_FLAGS_NONE, -1, null_mut(), &mut error)
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { executable_from_reply(reply) })
+        }
+    }
+
+    pub fn set_allowed_owners(&self, executables: Option<Vec<PathBuf>>) {
+        let owners =
+            match executables {
+                Some(executables) => Box::into_raw(Box::new(executables)) as glib_ffi::gpointer,
+                None => null_mut(),
+            };
+        unsafe {
+            gobject_ffi::g_object_set_data_full(self.to_glib_none().0 as *mut _, ALLOWED_OWNERS_KEY.as_ptr() as *const c_char,
+                owners, Some(destroy_allowed_owners));
+        }
+    }
+
+    pub fn verify_owner<F: Fn(Result<(), PasswordError>) + 'static>(&self, callback: F) {
+        let allowed_owners =
+            match self.get_allowed_owners() {
+                Some(allowed_owners) => allowed_owners,
+                None => return callback(Ok(())),
+            };
+        self.get_owner_executable(move |executable| {
+            callback(executable.map_err(Glib).and_then(|executable| check_owner(&allowed_owners, executable)))
+        });
+    }
+
+    pub fn verify_owner_sync(&self) -> Result<(), PasswordError> {
+        match self.get_allowed_owners() {
+            Some(allowed_owners) => check_owner(&allowed_owners, self.get_owner_executable_sync().map_err(Glib)?),
+            None => Ok(()),
+        }
+    }
+
+    fn get_connection(&self) -> *mut gio_sys::GDBusConnection {
+        unsafe { gio_sys::g_dbus_proxy_get_connection(self.to_glib_none().0 as *mut _) }
+    }
+}
+
+fn check_owner(allowed_owners: &[PathBuf], executable: Option<PathBuf>) -> Result<(), PasswordError> {
+    match executable {
+        Some(ref executable) if allowed_owners.contains(executable) => Ok(()),
+        _ => Err(UntrustedOwner(executable)),
+    }
+}
+
+unsafe extern "C" fn destroy_allowed_owners(owners: glib_ffi::gpointer) {
+    Box::<Vec<PathBuf>>::from_raw(owners as *mut _);
+}
+
+// The executable cannot be trusted if it is not readable, so it is reported as unknown.
+unsafe fn executable_from_reply(reply: *mut glib_ffi::GVariant) -> Option<PathBuf> {
+    let child = glib_ffi::g_variant_get_child_value(reply, 0);
+    let process_id = glib_ffi::g_variant_get_uint32(child);
+    glib_ffi::g_variant_unref(child);
+    glib_ffi::g_variant_unref(reply);
+    fs::read_link(format!("/proc/{}/exe", process_id)).ok()
+}
+
+unsafe fn process_id_parameters(owner: &str) -> *mut glib_ffi::GVariant {
+    let owner = CString::new(owner).unwrap();
+    let mut children = [glib_ffi::g_variant_new_string(owner.as_ptr())];
+    glib_ffi::g_variant_new_tuple(children.as_mut_ptr(), children.len())
+}
+
+unsafe extern "C" fn service_get_owner_executable_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let reply = gio_sys::g_dbus_connection_call_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(executable_from_reply(reply))
+        };
+    let f: &Box<Fn(Result<Option<PathBuf>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
@@ -21,173 +21,362 @@
 
 use std::collections::HashMap;
 use std::ffi::CString;
-use std::mem::transmute;
+use std::path::PathBuf;
 use std::ptr::null_mut;
+use std::rc::Rc;
 
 use ffi;
-use gio_sys;
 use glib::error;
-use glib::translate::{ToGlib, ToGlibPtr, from_glib_full, from_glib_none};
-use glib_ffi::{self, GHashTable, g_hash_table_insert};
-use gobject_ffi;
+use glib_ffi::{GHashTable, g_hash_table_insert, g_hash_table_new, g_str_equal, g_str_hash};
 use libc::c_void;
 
-use AsyncReadyCallback;
+use attachment::{self, Chunk, attachment_attributes};
+use Collection;
+use COLLECTION_NONE;
+use collection::search_collection;
+use Error::{NoSuchObject, Protocol};
+use error::{new_error, with_attempts};
 use Item;
+use item::delete_items;
+use RetryPolicy;
 use Schema;
-use SEARCH_ALL;
-use SEARCH_LOAD_SECRETS;
-use SEARCH_UNLOCK;
+use SearchOptions;
+use Service;
+use SERVICE_NONE;
+use SERVICE_OPEN_SESSION;
+use SessionPolicy;
+use TempItem;
+use Value;
 use self::PasswordError::*;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum PasswordError {
+    Conflict,
+    CorruptedAttachment,
     Glib(error::Error),
+    InsecureSession(String),
     NoResult,
+    Retried(u32, Box<PasswordError>),
+    UntrustedOwner(Option<PathBuf>),
 }
 
+#[derive(Clone)]
 pub struct Passwords {
     pub collection: Option<String>,
+    pub retry_policy: RetryPolicy,
     pub schema: Schema,
+    pub service: Option<Service>,
+    pub session_policy: SessionPolicy,
 }
 
 impl Passwords {
     pub fn new(schema: Schema) -> Self {
         Passwords {
             collection: None,
+            retry_policy: RetryPolicy::new(),
             schema: schema,
+            service: None,
+            session_policy: SessionPolicy::Any,
         }
     }
 
-    pub fn clear<F: Fn(Result<bool, error::Error>) + 'static>(&self, attributes: &HashMap<String, String>, callback: F) {
-        let trampoline: AsyncReadyCallback = unsafe { transmute(password_clear_trampoline as usize) };
-        type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
-        let f: Box<BoxedFn> = Box::new(Box::new(callback));
-        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
-        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(self.schema.to_glib_none().0, attributes) };
-        unsafe {
-            ffi::secret_password_clearv(
-                self.schema.to_glib_none().0, hash_table, null_mut(), trampoline, user_data
-            );
+    pub fn for_collection(schema: Schema, collection: &Collection) -> Self {
+        Passwords {
+            collection: Some(collection.get_object_path()),
+            retry_policy: RetryPolicy::new(),
+            schema: schema,
+            service: collection.get_service(),
+            session_policy: SessionPolicy::Any,
         }
     }
 
-    pub fn lookup<F: Fn(Result<String, PasswordError>) + 'static>(&self, attributes: &HashMap<String, String>, callback: F) {
-        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(self.schema.to_glib_none().0, attributes) };
-        let trampoline: AsyncReadyCallback = unsafe { transmute(password_lookup_trampoline as usize) };
-        type BoxedFn = Box<Fn(Result<String, PasswordError>) + 'static>;
-        let f: Box<BoxedFn> = Box::new(Box::new(callback));
-        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
-        unsafe {
-            ffi::secret_password_lookupv(
-                self.schema.to_glib_none().0, hash_table, null_mut(), trampoline, user_data
-            );
+    pub fn for_service(schema: Schema, service: &Service) -> Self {
+        Passwords {
+            collection: None,
+            retry_policy: RetryPolicy::new(),
+            schema: schema,
+            service: Some(service.clone()),
+            session_policy: SessionPolicy::Any,
         }
     }
 
+    pub fn clear<F: Fn(Result<bool, error::Error>) + 'static>(&self, attributes: &HashMap<String, String>, callback: F) {
+        let attributes = attributes.clone();
+        self.with_checks_for_write(move |passwords, service, callback| {
+            passwords.delete_items(service, Some(&passwords.schema), &attributes, move |result| callback(result));
+        }, move |result| callback(result.map_err(to_glib_error)));
+    }
+
+    pub fn clear_attachment<F: Fn(Result<bool, PasswordError>) + 'static>(&self, attributes: &HashMap<String, String>, callback: F) {
+        let attributes = attachment_attributes(&self.schema, attributes);
+        self.with_checks_for_write(move |passwords, service, callback| {
+            let passwords = passwords.clone();
+            let service = service.clone();
+            attachment::clear(move |attributes, callback| passwords.search_chunks(&service, attributes, callback), &attributes,
+                Box::new(move |result: Result<bool, PasswordError>| callback(result)));
+        }, callback);
+    }
+
+    pub fn lookup<F: Fn(Result<String, PasswordError>) + 'static>(&self, attributes: &HashMap<String, String>, callback: F) {
+        let attributes = attributes.clone();
+        self.with_checks(move |passwords, service, callback| {
+            passwords.search_items(service, Some(&passwords.schema), &attributes, &SearchOptions::new().all(false), move |items| {
+                callback(items.and_then(|items| password_from_value(items.into_iter().next().and_then(|item| item.get_secret()))))
+            });
+        }, callback);
+    }
+
+    pub fn lookup_attachment<F: Fn(Result<Vec<u8>, PasswordError>) + 'static>(&self, attributes: &HashMap<String, String>, callback: F) {
+        let attributes = attachment_attributes(&self.schema, attributes);
+        self.with_checks(move |passwords, service, callback| {
+            let passwords = passwords.clone();
+            let service = service.clone();
+            attachment::lookup(move |attributes, callback| {
+                passwords.search_items(&service, None, attributes, &SearchOptions::new().load_secrets(false), move |items| callback(items))
+            }, &attributes, Box::new(move |result: Result<Vec<u8>, PasswordError>| callback(result)));
+        }, callback);
+    }
+
+    pub fn replace_if_unchanged<F: Fn(Result<bool, PasswordError>) + 'static>(&self, attributes: &HashMap<String, String>, modified: u64, password: Option<&str>, new_password: &str, callback: F) {
+        let attributes = attributes.clone();
+        let password = password.map(|password| password.to_string());
+        let value = Value::new(new_password.as_bytes(), "text/plain");
+        self.with_checks_for_write(move |passwords, service, callback| {
+            let password = password.clone();
+            let value = value.clone();
+            passwords.search_items(service, Some(&passwords.schema), &attributes, &SearchOptions::new().all(false), move |items| {
+                match items.map(|items| items.into_iter().next()) {
+                    Ok(Some(item)) => {
+                        let callback = callback.clone();
+                        let password = password.as_ref().map(|password| password.as_bytes());
+                        item.replace_if_unchanged(modified, password, &value, move |result| callback(result));
+                    },
+                    Ok(None) => callback(Err(NoResult)),
+                    Err(error) => callback(Err(error)),
+                }
+            });
+        }, callback);
+    }
+
     pub fn search<F: Fn(Result<Vec<Item>, error::Error>) + 'static>(&self, attributes: &HashMap<String, String>, callback: F) {
-        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(self.schema.to_glib_none().0, attributes) };
-        let trampoline: AsyncReadyCallback = unsafe { transmute(service_search_trampoline as usize) };
-        type BoxedFn = Box<Fn(Result<Vec<Item>, ::glib::error::Error>) + 'static>;
-        let f: Box<BoxedFn> = Box::new(Box::new(callback));
-        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
-
-        unsafe {
-            ffi::secret_service_search(null_mut(), self.schema.to_glib_none().0,
-                hash_table, (SEARCH_ALL | SEARCH_LOAD_SECRETS | SEARCH_UNLOCK).to_glib(), null_mut(), trampoline, user_data)
-        }
+        self.search_with_options(attributes, &SearchOptions::new(), callback);
+    }
+
+    pub fn search_with_options<F: Fn(Result<Vec<Item>, error::Error>) + 'static>(&self, attributes: &HashMap<String, String>, options: &SearchOptions, callback: F) {
+        let attributes = attributes.clone();
+        let options = *options;
+        self.with_checks(move |passwords, service, callback| {
+            passwords.search_items(service, Some(&passwords.schema), &attributes, &options, move |items| callback(items));
+        }, move |items| callback(items.map_err(to_glib_error)));
     }
 
     pub fn store<F: Fn(Result<bool, error::Error>) + 'static>(&self, label: &str, password: &str, attributes: &HashMap<String, String>, callback: F) {
-        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(self.schema.to_glib_none().0, attributes) };
-        let trampoline: AsyncReadyCallback = unsafe { transmute(password_store_trampoline as usize) };
-        type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
-        let f: Box<BoxedFn> = Box::new(Box::new(callback));
-        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
-        let label = CString::new(label).unwrap();
-        let password = CString::new(password).unwrap();
-        unsafe {
-            ffi::secret_password_storev(
-                self.schema.to_glib_none().0, hash_table, self.collection.to_glib_none().0,
-                label.as_ptr(), password.as_ptr(), ::std::ptr::null_mut(), trampoline, user_data,
-            );
-        }
+        let label = label.to_string();
+        let value = Value::new(password.as_bytes(), "text/plain");
+        let attributes = attributes.clone();
+        self.with_checks_for_write(move |passwords, service, callback| {
+            let collection = passwords.collection.as_ref().map(|collection| collection.as_str());
+            service.store(Some(&passwords.schema), &attributes, collection, &label, &value, move |result| callback(result.map_err(Glib)));
+        }, move |result| callback(result.map_err(to_glib_error)));
+    }
+
+    pub fn store_attachment<F: Fn(Result<bool, PasswordError>) + 'static>(&self, label: &str, data: &[u8], attributes: &HashMap<String, String>, callback: F) {
+        let label = label.to_string();
+        let data = data.to_vec();
+        let attributes = attachment_attributes(&self.schema, attributes);
+        self.with_checks_for_write(move |passwords, service, callback| {
+            let store_passwords = passwords.clone();
+            let store_service = service.clone();
+            let search_passwords = passwords.clone();
+            let search_service = service.clone();
+            attachment::replace(move |chunk, callback| store_passwords.store_chunk(&store_service, chunk, move |result| callback(result)),
+                move |attributes, callback| search_passwords.search_chunks(&search_service, attributes, callback), &attributes, &label, &data,
+                Box::new(move |result: Result<bool, PasswordError>| callback(result)));
+        }, callback);
+    }
+
+    pub fn store_temporary<F: Fn(Result<TempItem, PasswordError>) + 'static>(&self, label: &str, password: &str, attributes: &HashMap<String, String>, callback: F) {
+        let label = label.to_string();
+        let password = password.to_string();
+        let attributes = attributes.clone();
+        self.with_checks_for_write(move |passwords, service, callback| {
+            let schema = passwords.schema.clone();
+            let label = label.clone();
+            let password = password.clone();
+            let attributes = attributes.clone();
+            passwords.with_collection(service, move |collection| {
+                match collection {
+                    Ok(collection) => {
+                        let callback = callback.clone();
+                        collection.item_create_temporary(&schema, &label, &password, &attributes, move |item| callback(item.map_err(Glib)));
+                    },
+                    Err(error) => callback(Err(Glib(error))),
+                }
+            });
+        }, callback);
     }
-}
 
-unsafe extern "C" fn password_clear_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
-    callback_guard!();
-    let mut error = null_mut();
-    let result = ffi::secret_password_clear_finish(result, &mut error);
-    let value =
-        if result != 0 {
-            Ok(true)
+    // The owner is verified by the Service, Collection and Item calls themselves, so only the
+    // session policy of these passwords is checked here.
+    fn check_session<F: Fn(Result<(), PasswordError>) + 'static>(&self, service: &Service, callback: F) {
+        if self.session_policy == SessionPolicy::Any {
+            return callback(Ok(()));
         }
-        else {
-            Err(from_glib_full(error))
+        service.check_session(self.session_policy, move |result| callback(result.map(|_| ())));
+    }
+
+    // Every operation goes through a service, the shared one unless another was given, so that
+    // its checks always run. Each attempt checks the session again since the service could have
+    // been replaced in between.
+    fn checked_service<F: Fn(Result<Service, PasswordError>) + 'static>(&self, callback: F) {
+        let callback = Rc::new(callback);
+        let check = {
+            let passwords = self.clone();
+            let callback = callback.clone();
+            move |service: Service| {
+                let callback = callback.clone();
+                let checked_service = service.clone();
+                passwords.check_session(&service, move |result| callback(result.map(|_| checked_service.clone())));
+            }
         };
-    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
-    f(value)
-}
+        if let Some(ref service) = self.service {
+            return check(service.clone());
+        }
+        let flags = if self.session_policy == SessionPolicy::Any { SERVICE_NONE } else { SERVICE_OPEN_SESSION };
+        Service::get_with_flags(flags, move |service| {
+            match service {
+                Ok(service) => check(service),
+                Err(error) => callback(Err(Glib(error))),
+            }
+        });
+    }
 
-unsafe extern "C" fn password_lookup_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
-    callback_guard!();
-    let mut error = null_mut();
-    let result = ffi::secret_password_lookup_finish(result, &mut error);
-    let value =
-        if !error.is_null() {
-            Err(Glib(from_glib_full(error)))
+    fn delete_items<F: Fn(Result<bool, PasswordError>) + 'static>(&self, service: &Service, schema: Option<&Schema>, attributes: &HashMap<String, String>, callback: F) {
+        let callback = Rc::new(callback);
+        self.search_items(service, schema, attributes, &SearchOptions::new().load_secrets(false).unlock(false), move |items| {
+            match items {
+                Ok(items) => {
+                    let callback = callback.clone();
+                    delete_items(items, move |result| callback(result.map_err(Glib)));
+                },
+                Err(error) => callback(Err(error)),
+            }
+        });
+    }
+
+    fn search_chunks(&self, service: &Service, attributes: &HashMap<String, String>, callback: Box<Fn(Result<Vec<Item>, PasswordError>)>) {
+        self.search_items(service, None, attributes, &SearchOptions::new().load_secrets(false).unlock(false), move |items| callback(items));
+    }
+
+    fn search_items<F: Fn(Result<Vec<Item>, PasswordError>) + 'static>(&self, service: &Service, schema: Option<&Schema>, attributes: &HashMap<String, String>, options: &SearchOptions, callback: F) {
+        if self.collection.is_none() {
+            return service.search(schema, attributes, options, move |items| callback(items.map_err(Glib)));
         }
-        else if !result.is_null() {
-            let password = Ok(from_glib_none(result));
-            ffi::secret_password_free(result);
-            password
+        let schema = schema.cloned();
+        let attributes = attributes.clone();
+        let options = *options;
+        let callback = Rc::new(callback);
+        self.with_collection(service, move |collection| {
+            match collection {
+                Ok(collection) => {
+                    let callback = callback.clone();
+                    search_collection(&collection, schema.as_ref(), &attributes, &options, move |items| callback(items.map_err(Glib)));
+                },
+                Err(error) => callback(Err(Glib(error))),
+            }
+        });
+    }
+
+    fn store_chunk<F: Fn(Result<bool, error::Error>) + 'static>(&self, service: &Service, chunk: &Chunk, callback: F) {
+        let collection = self.collection.as_ref().map(|collection| collection.as_str());
+        service.store(None, &chunk.attributes, collection, &chunk.label, &chunk.value, callback);
+    }
+
+    // A read is idempotent, so the whole operation is retried.
+    fn with_checks<T: 'static, O, F>(&self, operation: O, callback: F)
+        where O: Fn(&Passwords, &Service, Rc<Fn(Result<T, PasswordError>)>) + 'static,
+              F: Fn(Result<T, PasswordError>) + 'static,
+    {
+        let passwords = self.clone();
+        let operation = Rc::new(operation);
+        self.retry_policy.run(move |callback| {
+            let operation = operation.clone();
+            let checked_passwords = passwords.clone();
+            passwords.checked_service(move |service| {
+                match service {
+                    Ok(service) => operation(&checked_passwords, &service, callback.clone()),
+                    Err(error) => callback(Err(error)),
+                }
+            });
+        }, callback);
+    }
+
+    // Retrying a write after a lost reply could apply it twice, creating a duplicate item, so only
+    // acquiring and checking the service is retried.
+    fn with_checks_for_write<T: 'static, O, F>(&self, operation: O, callback: F)
+        where O: Fn(&Passwords, &Service, Rc<Fn(Result<T, PasswordError>)>) + 'static,
+              F: Fn(Result<T, PasswordError>) + 'static,
+    {
+        let passwords = self.clone();
+        let checked_passwords = self.clone();
+        let callback: Rc<Fn(Result<T, PasswordError>)> = Rc::new(callback);
+        self.retry_policy.run(move |callback| {
+            passwords.checked_service(move |service| callback(service));
+        }, move |service| {
+            match service {
+                Ok(service) => operation(&checked_passwords, &service, callback.clone()),
+                Err(error) => callback(Err(error)),
+            }
+        });
+    }
+
+    fn with_collection<F: Fn(Result<Collection, error::Error>) + 'static>(&self, service: &Service, callback: F) {
+        // Like libsecret, store into the default collection when none is given.
+        let collection = self.collection.clone().unwrap_or_else(|| "default".to_string());
+        if collection.starts_with('/') {
+            Collection::new_for_dbus_path(Some(service), &collection, COLLECTION_NONE, callback);
         }
         else {
-            Err(NoResult)
-        };
-    let f: &Box<Fn(Result<String, PasswordError>) + 'static> = &*(f as *const _);
-    f(value)
+            Collection::for_alias(Some(service), &collection.clone(), COLLECTION_NONE, move |result| {
+                callback(result.and_then(|found| found.ok_or_else(|| {
+                    new_error(NoSuchObject, &format!("No collection with the alias {}", collection))
+                })))
+            });
+        }
+    }
 }
 
-unsafe extern "C" fn password_store_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
-    callback_guard!();
-    let mut error = null_mut();
-    let result = ffi::secret_password_store_finish(result, &mut error);
-    let value =
-        if result != 0 {
-            Ok(true)
-        }
-        else {
-            Err(from_glib_full(error))
-        };
-    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
-    f(value)
+// The methods predating PasswordError report glib errors, so the other errors are converted to
+// the libsecret error domain.
+pub fn to_glib_error(error: PasswordError) -> error::Error {
+    match error {
+        Conflict => new_error(Protocol, "The item was modified"),
+        CorruptedAttachment => new_error(Protocol, "The attachment is corrupted"),
+        Glib(error) => error,
+        InsecureSession(algorithms) => new_error(Protocol, &format!("The session uses the insecure {} algorithms", algorithms)),
+        NoResult => new_error(NoSuchObject, "No matching item"),
+        Retried(attempts, error) => with_attempts(&to_glib_error(*error), attempts),
+        UntrustedOwner(Some(executable)) => new_error(Protocol, &format!("The Secret Service is owned by the untrusted {}", executable.display())),
+        UntrustedOwner(None) => new_error(Protocol, "The Secret Service is owned by an unknown executable"),
+    }
 }
 
-unsafe extern "C" fn service_search_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
-    callback_guard!();
-    let mut error = ::std::ptr::null_mut();
-    let result = ffi::secret_service_search_finish(null_mut(), result, &mut error);
-    let value =
-        if result.is_null() {
-            Err(from_glib_full(error))
-        }
-        else {
-            let mut items = vec![];
-            let mut list = result;
-            while !list.is_null() {
-                items.push(from_glib_none((*list).data as *mut _));
-                list = (*list).next;
-            }
-            Ok(items)
-        };
-    let f: &Box<Fn(Result<Vec<Item>, ::glib::error::Error>) + 'static> = &*(f as *const _);
-    f(value)
+// Any UTF-8 secret is returned, whatever its content type.
+fn password_from_value(value: Option<Value>) -> Result<String, PasswordError> {
+    match value {
+        Some(value) => String::from_utf8(value.get()).map_err(|_| Glib(new_error(Protocol, "The secret is not valid UTF-8 text"))),
+        None => Err(NoResult),
+    }
 }
 
 pub unsafe fn to_glib_string_hash_map(schema: *mut ffi::SecretSchema, hash_map: &HashMap<String, String>) -> (Vec<CString>, *mut GHashTable) {
-    let result = ffi::secret_attributes_build(schema, null_mut() as *mut c_void);
+    let result =
+        if schema.is_null() {
+            g_hash_table_new(Some(g_str_hash), Some(g_str_equal))
+        }
+        else {
+            ffi::secret_attributes_build(schema, null_mut() as *mut c_void)
+        };
     let mut strings = vec![];
     for (key, value) in hash_map {
         let key = CString::new(key.clone()).unwrap();
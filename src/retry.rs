@@ -0,0 +1,220 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::rc::Rc;
+use std::time::Duration;
+use std::u32;
+
+use gio_sys;
+use glib::{Continue, timeout_add};
+
+use error::is_dbus_error;
+use PasswordError::{self, Glib, Retried};
+use Service;
+use ServiceFlags;
+use self::RetryableError::*;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RetryableError {
+    Disconnected,
+    NameHasNoOwner,
+    NoReply,
+    ServiceUnknown,
+    TimedOut,
+    Timeout,
+}
+
+impl RetryableError {
+    fn to_dbus_error(&self) -> gio_sys::GDBusError {
+        match *self {
+            Disconnected => gio_sys::G_DBUS_ERROR_DISCONNECTED,
+            NameHasNoOwner => gio_sys::G_DBUS_ERROR_NAME_HAS_NO_OWNER,
+            NoReply => gio_sys::G_DBUS_ERROR_NO_REPLY,
+            ServiceUnknown => gio_sys::G_DBUS_ERROR_SERVICE_UNKNOWN,
+            TimedOut => gio_sys::G_DBUS_ERROR_TIMED_OUT,
+            Timeout => gio_sys::G_DBUS_ERROR_TIMEOUT,
+        }
+    }
+}
+
+// Only Service::get_with_retry and the Passwords operations retry: their reads as a whole, their
+// writes only while acquiring and checking the service. The Service, Collection and Item methods
+// report the first error.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    attempts: u32,
+    backoff: Duration,
+    errors: Vec<RetryableError>,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        RetryPolicy {
+            attempts: 1,
+            backoff: Duration::from_millis(100),
+            errors: vec![NameHasNoOwner, NoReply, ServiceUnknown, TimedOut],
+            max_delay: Duration::from_secs(10),
+        }
+    }
+
+    pub fn attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn errors(mut self, errors: &[RetryableError]) -> Self {
+        self.errors = errors.to_vec();
+        self
+    }
+
+    pub fn get_attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub fn get_backoff(&self) -> Duration {
+        self.backoff
+    }
+
+    pub fn get_errors(&self) -> &[RetryableError] {
+        &self.errors
+    }
+
+    pub fn get_max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    pub fn is_retryable(&self, error: &PasswordError) -> bool {
+        match *error {
+            Glib(ref error) => self.errors.iter().any(|kind| is_dbus_error(error, kind.to_dbus_error())),
+            _ => false,
+        }
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn run<T: 'static, O, F>(&self, operation: O, callback: F)
+        where O: Fn(Rc<Fn(Result<T, PasswordError>)>) + 'static,
+              F: Fn(Result<T, PasswordError>) + 'static,
+    {
+        run_attempt(Rc::new(self.clone()), Rc::new(operation), 1, Rc::new(callback));
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new()
+    }
+}
+
+impl Service {
+    pub fn get_with_retry<F: Fn(Result<Service, PasswordError>) + 'static>(flags: ServiceFlags, policy: &RetryPolicy, callback: F) {
+        policy.run(move |callback| {
+            Service::get_with_flags(flags, move |service| callback(service.map_err(Glib)));
+        }, callback);
+    }
+}
+
+fn run_attempt<T: 'static>(policy: Rc<RetryPolicy>, operation: Rc<Fn(Rc<Fn(Result<T, PasswordError>)>)>, attempt: u32, callback: Rc<Fn(Result<T, PasswordError>)>) {
+    let next_operation = operation.clone();
+    operation(Rc::new(move |result: Result<T, PasswordError>| {
+        let retry =
+            match result {
+                Err(ref error) => attempt < policy.attempts && policy.is_retryable(error),
+                Ok(_) => false,
+            };
+        if retry {
+            let milliseconds = to_milliseconds(retry_delay(policy.backoff, policy.max_delay, attempt));
+            let policy = policy.clone();
+            let operation = next_operation.clone();
+            let callback = callback.clone();
+            timeout_add(milliseconds, move || {
+                run_attempt(policy.clone(), operation.clone(), attempt + 1, callback.clone());
+                Continue(false)
+            });
+        }
+        else if attempt > 1 {
+            callback(result.map_err(|error| Retried(attempt, Box::new(error))))
+        }
+        else {
+            callback(result)
+        }
+    }));
+}
+
+// The delay doubles after each failed attempt, up to the maximum delay.
+fn retry_delay(backoff: Duration, max_delay: Duration, attempt: u32) -> Duration {
+    let factor = 2u32.checked_pow(attempt - 1).unwrap_or(u32::MAX);
+    match backoff.checked_mul(factor) {
+        Some(delay) if delay < max_delay => delay,
+        _ => max_delay,
+    }
+}
+
+fn to_milliseconds(duration: Duration) -> u32 {
+    let milliseconds = duration.as_secs().saturating_mul(1000).saturating_add((duration.subsec_nanos() / 1_000_000) as u64);
+    if milliseconds > u32::MAX as u64 {
+        u32::MAX
+    }
+    else {
+        milliseconds as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::u32;
+
+    use super::{retry_delay, to_milliseconds};
+
+    #[test]
+    fn delay_doubles_after_each_attempt() {
+        let backoff = Duration::from_millis(100);
+        let max_delay = Duration::from_secs(10);
+        assert_eq!(retry_delay(backoff, max_delay, 1), Duration::from_millis(100));
+        assert_eq!(retry_delay(backoff, max_delay, 2), Duration::from_millis(200));
+        assert_eq!(retry_delay(backoff, max_delay, 4), Duration::from_millis(800));
+    }
+
+    #[test]
+    fn delay_is_capped() {
+        let backoff = Duration::from_millis(100);
+        let max_delay = Duration::from_secs(10);
+        assert_eq!(retry_delay(backoff, max_delay, 8), max_delay);
+        assert_eq!(retry_delay(backoff, max_delay, 40), max_delay);
+        assert_eq!(retry_delay(Duration::from_secs(u64::max_value()), max_delay, 2), max_delay);
+    }
+
+    #[test]
+    fn milliseconds_saturate() {
+        assert_eq!(to_milliseconds(Duration::from_millis(1500)), 1500);
+        assert_eq!(to_milliseconds(Duration::from_secs(u64::max_value())), u32::MAX);
+    }
+}
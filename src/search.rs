@@ -0,0 +1,90 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use SearchFlags;
+use SEARCH_ALL;
+use SEARCH_LOAD_SECRETS;
+use SEARCH_UNLOCK;
+
+#[derive(Clone, Copy, Debug)]
+pub struct SearchOptions {
+    flags: SearchFlags,
+}
+
+impl SearchOptions {
+    pub fn new() -> Self {
+        SearchOptions {
+            flags: SEARCH_ALL | SEARCH_LOAD_SECRETS | SEARCH_UNLOCK,
+        }
+    }
+
+    pub fn all(self, all: bool) -> Self {
+        self.with_flag(SEARCH_ALL, all)
+    }
+
+    pub fn get_flags(&self) -> SearchFlags {
+        self.flags
+    }
+
+    pub fn load_secrets(self, load_secrets: bool) -> Self {
+        self.with_flag(SEARCH_LOAD_SECRETS, load_secrets)
+    }
+
+    pub fn unlock(self, unlock: bool) -> Self {
+        self.with_flag(SEARCH_UNLOCK, unlock)
+    }
+
+    fn with_flag(mut self, flag: SearchFlags, enabled: bool) -> Self {
+        if enabled {
+            self.flags.insert(flag);
+        }
+        else {
+            self.flags.remove(flag);
+        }
+        self
+    }
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use SEARCH_ALL;
+    use SEARCH_LOAD_SECRETS;
+    use SEARCH_UNLOCK;
+    use super::SearchOptions;
+
+    #[test]
+    fn defaults_enable_every_flag() {
+        assert_eq!(SearchOptions::new().get_flags(), SEARCH_ALL | SEARCH_LOAD_SECRETS | SEARCH_UNLOCK);
+    }
+
+    #[test]
+    fn flags_are_toggled() {
+        let options = SearchOptions::new().all(false).unlock(false);
+        assert_eq!(options.get_flags(), SEARCH_LOAD_SECRETS);
+        assert_eq!(options.unlock(true).load_secrets(false).get_flags(), SEARCH_UNLOCK);
+    }
+}
@@ -19,8 +19,11 @@
  * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
  */
 
-use std::mem::transmute;
-use std::ptr::null_mut;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::mem::{transmute, uninitialized};
+use std::ptr::{null, null_mut};
+use std::rc::Rc;
 
 use ffi;
 use gio_sys;
@@ -28,19 +31,259 @@ use glib::error;
 use glib::translate::{FromGlib, ToGlib, ToGlibPtr, from_glib_full, from_glib_none};
 use glib_ffi;
 use gobject_ffi;
-use libc::c_void;
+use libc::{c_char, c_void};
 
+use access::{check_access, check_access_sync};
 use AsyncReadyCallback;
+use Collection;
+use CollectionCreateFlags;
+use Item;
+use ItemCreateFlags;
+use LockReport;
+use lock::{read_locked, read_locked_sync, to_glib_object_list};
+use PasswordError::{self, Glib};
+use Schema;
+use SearchOptions;
+use SecretObject;
 use Service;
+use ServiceFlags;
+use SessionPolicy;
+use to_glib_string_hash_map;
+use Value;
+use Variant;
+use variant::to_glib_variant_hash_map;
+use watch::mark_shared;
 use SERVICE_NONE;
 
 impl Service {
+    pub fn check_session<F: Fn(Result<String, PasswordError>) + 'static>(&self, policy: SessionPolicy, callback: F) {
+        self.ensure_session(move |algorithms| {
+            callback(algorithms.map_err(Glib).and_then(|algorithms| policy.check(&algorithms).map(|_| algorithms)))
+        });
+    }
+
+    pub fn check_session_sync(&self, policy: SessionPolicy) -> Result<String, PasswordError> {
+        let algorithms = self.ensure_session_sync().map_err(Glib)?;
+        policy.check(&algorithms)?;
+        Ok(algorithms)
+    }
+
+    pub fn clear<F: Fn(Result<bool, error::Error>) + 'static>(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>, callback: F) {
+        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, attributes) };
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_clear_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_service_clear(self.to_glib_none().0, schema.to_glib_none().0, hash_table, null_mut(), trampoline, user_data) };
+    }
+
+    pub fn clear_sync(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>) -> Result<bool, error::Error> {
+        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, attributes) };
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_service_clear_sync(self.to_glib_none().0, schema.to_glib_none().0, hash_table, null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        }
+    }
+
+    pub fn create_collection_dbus_path<F: Fn(Result<String, error::Error>) + 'static>(&self, properties: &HashMap<String, Variant>, alias: Option<&str>, flags: CollectionCreateFlags, callback: F) {
+        let (_strings, hash_table) = unsafe { to_glib_variant_hash_map(properties) };
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_create_collection_dbus_path_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<String, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe {
+            ffi::secret_service_create_collection_dbus_path(self.to_glib_none().0, hash_table, alias.to_glib_none().0, flags.to_glib(), null_mut(), trampoline, user_data);
+            glib_ffi::g_hash_table_unref(hash_table);
+        }
+    }
+
+    pub fn create_collection_dbus_path_sync(&self, properties: &HashMap<String, Variant>, alias: Option<&str>, flags: CollectionCreateFlags) -> Result<String, error::Error> {
+        let (_strings, hash_table) = unsafe { to_glib_variant_hash_map(properties) };
+        let mut error = null_mut();
+        let result = unsafe {
+            let result = ffi::secret_service_create_collection_dbus_path_sync(self.to_glib_none().0, hash_table, alias.to_glib_none().0, flags.to_glib(), null_mut(), &mut error);
+            glib_ffi::g_hash_table_unref(hash_table);
+            result
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(result) })
+        }
+    }
+
+    pub fn create_item_dbus_path<F: Fn(Result<String, error::Error>) + 'static>(&self, collection_path: &str, properties: &HashMap<String, Variant>, value: &Value, flags: ItemCreateFlags, callback: F) {
+        let service = self.clone();
+        let collection_path = CString::new(collection_path).unwrap();
+        let properties = properties.clone();
+        let value = value.clone();
+        check_access(Some(self), move |callback| {
+            let (_strings, hash_table) = unsafe { to_glib_variant_hash_map(&properties) };
+            let trampoline: AsyncReadyCallback = unsafe { transmute(service_create_item_dbus_path_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<String, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe {
+                ffi::secret_service_create_item_dbus_path(service.to_glib_none().0, collection_path.as_ptr(), hash_table,
+                    value.to_glib_none().0, flags.to_glib(), null_mut(), trampoline, user_data);
+            }
+        }, callback);
+    }
+
+    pub fn create_item_dbus_path_sync(&self, collection_path: &str, properties: &HashMap<String, Variant>, value: &Value, flags: ItemCreateFlags) -> Result<String, error::Error> {
+        check_access_sync(Some(self))?;
+        let collection_path = CString::new(collection_path).unwrap();
+        let (_strings, hash_table) = unsafe { to_glib_variant_hash_map(properties) };
+        let mut error = null_mut();
+        let result = unsafe {
+            ffi::secret_service_create_item_dbus_path_sync(self.to_glib_none().0, collection_path.as_ptr(), hash_table,
+                value.to_glib_none().0, flags.to_glib(), null_mut(), &mut error)
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(result) })
+        }
+    }
+
+    pub fn decode_dbus_secret(&self, value: &Variant) -> Result<Option<Value>, error::Error> {
+        check_access_sync(Some(self))?;
+        Ok(unsafe { from_glib_full(ffi::secret_service_decode_dbus_secret(self.to_glib_none().0, value.to_glib_none().0)) })
+    }
+
+    pub fn delete_item_dbus_path<F: Fn(Result<bool, error::Error>) + 'static>(&self, item_path: &str, callback: F) {
+        let item_path = CString::new(item_path).unwrap();
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_delete_item_dbus_path_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_service_delete_item_dbus_path(self.to_glib_none().0, item_path.as_ptr(), null_mut(), trampoline, user_data) };
+    }
+
+    pub fn delete_item_dbus_path_sync(&self, item_path: &str) -> Result<bool, error::Error> {
+        let item_path = CString::new(item_path).unwrap();
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_service_delete_item_dbus_path_sync(self.to_glib_none().0, item_path.as_ptr(), null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        }
+    }
+
+    pub fn encode_dbus_secret(&self, value: &Value) -> Result<Option<Variant>, error::Error> {
+        check_access_sync(Some(self))?;
+        unsafe {
+            let variant = ffi::secret_service_encode_dbus_secret(self.to_glib_none().0, value.to_glib_none().0);
+            if variant.is_null() {
+                Ok(None)
+            }
+            else {
+                Ok(Some(from_glib_full(glib_ffi::g_variant_ref_sink(variant))))
+            }
+        }
+    }
+
+    pub fn ensure_session<F: Fn(Result<String, error::Error>) + 'static>(&self, callback: F) {
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_ensure_session_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<String, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_service_ensure_session(self.to_glib_none().0, null_mut(), trampoline, user_data) };
+    }
+
+    pub fn ensure_session_sync(&self) -> Result<String, error::Error> {
+        let mut error = null_mut();
+        unsafe { ffi::secret_service_ensure_session_sync(self.to_glib_none().0, null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(self.get_session_algorithms().unwrap_or_default())
+        }
+    }
+
     pub fn get<F: Fn(Result<Service, error::Error>) + 'static>(callback: F) {
+        Service::get_with_flags(SERVICE_NONE, callback);
+    }
+
+    pub fn get_secret_for_dbus_path<F: Fn(Result<Option<Value>, error::Error>) + 'static>(&self, item_path: &str, callback: F) {
+        let service = self.clone();
+        let item_path = CString::new(item_path).unwrap();
+        check_access(Some(self), move |callback| {
+            let trampoline: AsyncReadyCallback = unsafe { transmute(service_get_secret_for_dbus_path_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<Option<Value>, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe { ffi::secret_service_get_secret_for_dbus_path(service.to_glib_none().0, item_path.as_ptr(), null_mut(), trampoline, user_data) };
+        }, callback);
+    }
+
+    pub fn get_secret_for_dbus_path_sync(&self, item_path: &str) -> Result<Option<Value>, error::Error> {
+        check_access_sync(Some(self))?;
+        let item_path = CString::new(item_path).unwrap();
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_service_get_secret_for_dbus_path_sync(self.to_glib_none().0, item_path.as_ptr(), null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(result) })
+        }
+    }
+
+    pub fn get_secrets_for_dbus_paths<F: Fn(Result<HashMap<String, Value>, error::Error>) + 'static>(&self, item_paths: &[String], callback: F) {
+        let service = self.clone();
+        let item_paths = item_paths.to_vec();
+        check_access(Some(self), move |callback| {
+            let (_strings, item_paths) = to_glib_path_array(&item_paths);
+            let trampoline: AsyncReadyCallback = unsafe { transmute(service_get_secrets_for_dbus_paths_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<HashMap<String, Value>, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe { ffi::secret_service_get_secrets_for_dbus_paths(service.to_glib_none().0, item_paths.as_ptr() as *mut _, null_mut(), trampoline, user_data) };
+        }, callback);
+    }
+
+    pub fn get_secrets_for_dbus_paths_sync(&self, item_paths: &[String]) -> Result<HashMap<String, Value>, error::Error> {
+        check_access_sync(Some(self))?;
+        let (_strings, item_paths) = to_glib_path_array(item_paths);
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_service_get_secrets_for_dbus_paths_sync(self.to_glib_none().0, item_paths.as_ptr() as *mut _, null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_value_hash_map(result) })
+        }
+    }
+
+    pub fn get_sync(flags: ServiceFlags) -> Result<Service, error::Error> {
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_service_get_sync(flags.to_glib(), null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            let service = unsafe { from_glib_full(result) };
+            mark_shared(&service);
+            Ok(service)
+        }
+    }
+
+    pub fn get_with_flags<F: Fn(Result<Service, error::Error>) + 'static>(flags: ServiceFlags, callback: F) {
         let trampoline: AsyncReadyCallback = unsafe { transmute(service_get_trampoline as usize) };
         type BoxedFn = Box<Fn(Result<Service, error::Error>) + 'static>;
         let f: Box<BoxedFn> = Box::new(Box::new(callback));
         let user_data: *mut c_void = Box::into_raw(f) as *mut _;
-        unsafe { ffi::secret_service_get(SERVICE_NONE.to_glib(), null_mut(), trampoline, user_data) };
+        unsafe { ffi::secret_service_get(flags.to_glib(), null_mut(), trampoline, user_data) };
     }
 
     pub fn load_collections<F: FnOnce(Result<bool, error::Error>) + 'static>(&self, callback: F) {
@@ -50,6 +293,515 @@ impl Service {
         let user_data: *mut c_void = Box::into_raw(f) as *mut _;
         unsafe { ffi::secret_service_load_collections(self.to_glib_none().0, null_mut(), trampoline, user_data) };
     }
+
+    pub fn lock<F: Fn(Result<LockReport, error::Error>) + 'static>(&self, objects: &[SecretObject], callback: F) {
+        self.set_locked(objects, true, callback);
+    }
+
+    pub fn lock_dbus_paths<F: Fn(Result<Vec<String>, error::Error>) + 'static>(&self, paths: &[String], callback: F) {
+        let (_strings, paths) = to_glib_path_array(paths);
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_lock_dbus_paths_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<Vec<String>, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_service_lock_dbus_paths(self.to_glib_none().0, paths.as_ptr() as *mut _, null_mut(), trampoline, user_data) };
+    }
+
+    pub fn lock_dbus_paths_sync(&self, paths: &[String]) -> Result<Vec<String>, error::Error> {
+        let (_strings, paths) = to_glib_path_array(paths);
+        let mut changed = null_mut();
+        let mut error = null_mut();
+        unsafe { ffi::secret_service_lock_dbus_paths_sync(self.to_glib_none().0, paths.as_ptr() as *mut _, null_mut(), &mut changed, &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_path_array(changed) })
+        }
+    }
+
+    pub fn lock_sync(&self, objects: &[SecretObject]) -> Result<LockReport, error::Error> {
+        self.set_locked_sync(objects, true)
+    }
+
+    pub fn lookup<F: Fn(Result<Option<Value>, error::Error>) + 'static>(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>, callback: F) {
+        let service = self.clone();
+        let schema = schema.cloned();
+        let attributes = attributes.clone();
+        check_access(Some(self), move |callback| {
+            let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, &attributes) };
+            let trampoline: AsyncReadyCallback = unsafe { transmute(service_lookup_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<Option<Value>, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe { ffi::secret_service_lookup(service.to_glib_none().0, schema.to_glib_none().0, hash_table, null_mut(), trampoline, user_data) };
+        }, callback);
+    }
+
+    pub fn lookup_sync(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>) -> Result<Option<Value>, error::Error> {
+        check_access_sync(Some(self))?;
+        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, attributes) };
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_service_lookup_sync(self.to_glib_none().0, schema.to_glib_none().0, hash_table, null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(result) })
+        }
+    }
+
+    pub fn open<F: Fn(Result<Service, error::Error>) + 'static>(service_bus_name: Option<&str>, flags: ServiceFlags, callback: F) {
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_open_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<Service, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe {
+            ffi::secret_service_open(ffi::secret_service_get_type(), service_bus_name.to_glib_none().0, flags.to_glib(), null_mut(), trampoline, user_data);
+        }
+    }
+
+    pub fn open_sync(service_bus_name: Option<&str>, flags: ServiceFlags) -> Result<Service, error::Error> {
+        let mut error = null_mut();
+        let result = unsafe {
+            ffi::secret_service_open_sync(ffi::secret_service_get_type(), service_bus_name.to_glib_none().0, flags.to_glib(), null_mut(), &mut error)
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(result) })
+        }
+    }
+
+    pub fn read_alias_dbus_path<F: Fn(Result<Option<String>, error::Error>) + 'static>(&self, alias: &str, callback: F) {
+        let alias = CString::new(alias).unwrap();
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_read_alias_dbus_path_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<Option<String>, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_service_read_alias_dbus_path(self.to_glib_none().0, alias.as_ptr(), null_mut(), trampoline, user_data) };
+    }
+
+    pub fn read_alias_dbus_path_sync(&self, alias: &str) -> Result<Option<String>, error::Error> {
+        let alias = CString::new(alias).unwrap();
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_service_read_alias_dbus_path_sync(self.to_glib_none().0, alias.as_ptr(), null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_full(result) })
+        }
+    }
+
+    pub fn search<F: Fn(Result<Vec<Item>, error::Error>) + 'static>(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>, options: &SearchOptions, callback: F) {
+        let service = self.clone();
+        let schema = schema.cloned();
+        let attributes = attributes.clone();
+        let options = *options;
+        check_access(Some(self), move |callback| {
+            let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, &attributes) };
+            let trampoline: AsyncReadyCallback = unsafe { transmute(service_search_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<Vec<Item>, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe {
+                ffi::secret_service_search(service.to_glib_none().0, schema.to_glib_none().0,
+                    hash_table, options.get_flags().to_glib(), null_mut(), trampoline, user_data)
+            }
+        }, callback);
+    }
+
+    pub fn search_for_dbus_paths<F: Fn(Result<(Vec<String>, Vec<String>), error::Error>) + 'static>(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>, callback: F) {
+        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, attributes) };
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_search_for_dbus_paths_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<(Vec<String>, Vec<String>), error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_service_search_for_dbus_paths(self.to_glib_none().0, schema.to_glib_none().0, hash_table, null_mut(), trampoline, user_data) };
+    }
+
+    pub fn search_for_dbus_paths_sync(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>) -> Result<(Vec<String>, Vec<String>), error::Error> {
+        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, attributes) };
+        let mut unlocked = null_mut();
+        let mut locked = null_mut();
+        let mut error = null_mut();
+        unsafe {
+            ffi::secret_service_search_for_dbus_paths_sync(self.to_glib_none().0, schema.to_glib_none().0, hash_table,
+                null_mut(), &mut unlocked, &mut locked, &mut error);
+        }
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { (from_glib_path_array(unlocked), from_glib_path_array(locked)) })
+        }
+    }
+
+    pub fn search_sync(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>, options: &SearchOptions) -> Result<Vec<Item>, error::Error> {
+        check_access_sync(Some(self))?;
+        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, attributes) };
+        let mut error = null_mut();
+        let result = unsafe {
+            ffi::secret_service_search_sync(self.to_glib_none().0, schema.to_glib_none().0,
+                hash_table, options.get_flags().to_glib(), null_mut(), &mut error)
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_item_list(result) })
+        }
+    }
+
+    pub fn set_alias<F: Fn(Result<bool, error::Error>) + 'static>(&self, alias: &str, collection: Option<&Collection>, callback: F) {
+        let alias = CString::new(alias).unwrap();
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_set_alias_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_service_set_alias(self.to_glib_none().0, alias.as_ptr(), collection.to_glib_none().0, null_mut(), trampoline, user_data) };
+    }
+
+    pub fn set_alias_sync(&self, alias: &str, collection: Option<&Collection>) -> Result<bool, error::Error> {
+        let alias = CString::new(alias).unwrap();
+        let mut error = null_mut();
+        let result = unsafe { ffi::secret_service_set_alias_sync(self.to_glib_none().0, alias.as_ptr(), collection.to_glib_none().0, null_mut(), &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        }
+    }
+
+    pub fn set_alias_to_dbus_path<F: Fn(Result<bool, error::Error>) + 'static>(&self, alias: &str, collection_path: Option<&str>, callback: F) {
+        let alias = CString::new(alias).unwrap();
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_set_alias_to_dbus_path_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe {
+            ffi::secret_service_set_alias_to_dbus_path(self.to_glib_none().0, alias.as_ptr(), collection_path.to_glib_none().0,
+                null_mut(), trampoline, user_data);
+        }
+    }
+
+    pub fn set_alias_to_dbus_path_sync(&self, alias: &str, collection_path: Option<&str>) -> Result<bool, error::Error> {
+        let alias = CString::new(alias).unwrap();
+        let mut error = null_mut();
+        let result = unsafe {
+            ffi::secret_service_set_alias_to_dbus_path_sync(self.to_glib_none().0, alias.as_ptr(), collection_path.to_glib_none().0,
+                null_mut(), &mut error)
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        }
+    }
+
+    pub fn store<F: Fn(Result<bool, error::Error>) + 'static>(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>, collection: Option<&str>, label: &str, value: &Value, callback: F) {
+        let service = self.clone();
+        let schema = schema.cloned();
+        let attributes = attributes.clone();
+        let collection = collection.map(|collection| collection.to_string());
+        let label = CString::new(label).unwrap();
+        let value = value.clone();
+        check_access(Some(self), move |callback| {
+            let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, &attributes) };
+            let trampoline: AsyncReadyCallback = unsafe { transmute(service_store_trampoline as usize) };
+            type BoxedFn = Box<Fn(Result<bool, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(callback);
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe {
+                ffi::secret_service_store(
+                    service.to_glib_none().0, schema.to_glib_none().0, hash_table, collection.to_glib_none().0,
+                    label.as_ptr(), value.to_glib_none().0, null_mut(), trampoline, user_data,
+                );
+            }
+        }, callback);
+    }
+
+    pub fn store_sync(&self, schema: Option<&Schema>, attributes: &HashMap<String, String>, collection: Option<&str>, label: &str, value: &Value) -> Result<bool, error::Error> {
+        check_access_sync(Some(self))?;
+        let (_strings, hash_table) = unsafe { to_glib_string_hash_map(schema.to_glib_none().0, attributes) };
+        let label = CString::new(label).unwrap();
+        let mut error = null_mut();
+        let result = unsafe {
+            ffi::secret_service_store_sync(
+                self.to_glib_none().0, schema.to_glib_none().0, hash_table, collection.to_glib_none().0,
+                label.as_ptr(), value.to_glib_none().0, null_mut(), &mut error,
+            )
+        };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        }
+    }
+
+    pub fn unlock<F: Fn(Result<LockReport, error::Error>) + 'static>(&self, objects: &[SecretObject], callback: F) {
+        self.set_locked(objects, false, callback);
+    }
+
+    pub fn unlock_dbus_paths<F: Fn(Result<Vec<String>, error::Error>) + 'static>(&self, paths: &[String], callback: F) {
+        let (_strings, paths) = to_glib_path_array(paths);
+        let trampoline: AsyncReadyCallback = unsafe { transmute(service_unlock_dbus_paths_trampoline as usize) };
+        type BoxedFn = Box<Fn(Result<Vec<String>, error::Error>) + 'static>;
+        let f: Box<BoxedFn> = Box::new(Box::new(callback));
+        let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+        unsafe { ffi::secret_service_unlock_dbus_paths(self.to_glib_none().0, paths.as_ptr() as *mut _, null_mut(), trampoline, user_data) };
+    }
+
+    pub fn unlock_dbus_paths_sync(&self, paths: &[String]) -> Result<Vec<String>, error::Error> {
+        let (_strings, paths) = to_glib_path_array(paths);
+        let mut changed = null_mut();
+        let mut error = null_mut();
+        unsafe { ffi::secret_service_unlock_dbus_paths_sync(self.to_glib_none().0, paths.as_ptr() as *mut _, null_mut(), &mut changed, &mut error) };
+        if !error.is_null() {
+            Err(unsafe { from_glib_full(error) })
+        }
+        else {
+            Ok(unsafe { from_glib_path_array(changed) })
+        }
+    }
+
+    pub fn unlock_sync(&self, objects: &[SecretObject]) -> Result<LockReport, error::Error> {
+        self.set_locked_sync(objects, false)
+    }
+
+    // The service also reports the objects that were already in the requested state, so the
+    // report compares the states read before and after the call instead.
+    fn set_locked<F: Fn(Result<LockReport, error::Error>) + 'static>(&self, objects: &[SecretObject], locked: bool, callback: F) {
+        let service = self.clone();
+        let objects = objects.to_vec();
+        let callback = Rc::new(callback);
+        read_locked(objects.clone(), move |before| {
+            let before =
+                match before {
+                    Ok(before) => before,
+                    Err(error) => return callback(Err(error)),
+                };
+            let report_objects = objects.clone();
+            let callback = callback.clone();
+            let trampoline: AsyncReadyCallback =
+                if locked {
+                    unsafe { transmute(service_lock_trampoline as usize) }
+                }
+                else {
+                    unsafe { transmute(service_unlock_trampoline as usize) }
+                };
+            type BoxedFn = Box<Fn(Result<Vec<String>, error::Error>) + 'static>;
+            let f: Box<BoxedFn> = Box::new(Box::new(move |paths: Result<Vec<String>, error::Error>| {
+                if let Err(error) = paths {
+                    return callback(Err(error));
+                }
+                let objects = report_objects.clone();
+                let before = before.clone();
+                let callback = callback.clone();
+                read_locked(report_objects.clone(), move |after| {
+                    callback(after.map(|after| LockReport::new(objects.clone(), &before, &after)))
+                });
+            }));
+            let user_data: *mut c_void = Box::into_raw(f) as *mut _;
+            unsafe {
+                let list = to_glib_object_list(&objects);
+                if locked {
+                    ffi::secret_service_lock(service.to_glib_none().0, list, null_mut(), trampoline, user_data);
+                }
+                else {
+                    ffi::secret_service_unlock(service.to_glib_none().0, list, null_mut(), trampoline, user_data);
+                }
+                glib_ffi::g_list_free(list);
+            }
+        });
+    }
+
+    fn set_locked_sync(&self, objects: &[SecretObject], locked: bool) -> Result<LockReport, error::Error> {
+        let before = read_locked_sync(objects)?;
+        let mut error = null_mut();
+        unsafe {
+            let list = to_glib_object_list(objects);
+            if locked {
+                ffi::secret_service_lock_sync(self.to_glib_none().0, list, null_mut(), null_mut(), &mut error);
+            }
+            else {
+                ffi::secret_service_unlock_sync(self.to_glib_none().0, list, null_mut(), null_mut(), &mut error);
+            }
+            glib_ffi::g_list_free(list);
+        }
+        if !error.is_null() {
+            return Err(unsafe { from_glib_full(error) });
+        }
+        let after = read_locked_sync(objects)?;
+        Ok(LockReport::new(objects.to_vec(), &before, &after))
+    }
+}
+
+unsafe fn from_glib_item_list(list: *mut glib_ffi::GList) -> Vec<Item> {
+    let mut items = vec![];
+    let mut node = list;
+    while !node.is_null() {
+        items.push(from_glib_full((*node).data as *mut _));
+        node = (*node).next;
+    }
+    glib_ffi::g_list_free(list);
+    items
+}
+
+unsafe fn from_glib_path_array(paths: *mut *mut c_char) -> Vec<String> {
+    let mut result = vec![];
+    if !paths.is_null() {
+        let mut index = 0;
+        while !(*paths.offset(index)).is_null() {
+            result.push(from_glib_none(*paths.offset(index)));
+            index += 1;
+        }
+        glib_ffi::g_strfreev(paths);
+    }
+    result
+}
+
+unsafe fn from_glib_proxy_list(list: *mut glib_ffi::GList) -> Vec<String> {
+    let mut paths = vec![];
+    let mut node = list;
+    while !node.is_null() {
+        paths.push(from_glib_none(gio_sys::g_dbus_proxy_get_object_path((*node).data as *mut _)));
+        gobject_ffi::g_object_unref((*node).data as *mut _);
+        node = (*node).next;
+    }
+    glib_ffi::g_list_free(list);
+    paths
+}
+
+unsafe fn from_glib_value_hash_map(hash_table: *mut glib_ffi::GHashTable) -> HashMap<String, Value> {
+    let mut values = HashMap::new();
+    let mut key = null_mut();
+    let mut value = null_mut();
+    let mut iter: glib_ffi::GHashTableIter = uninitialized();
+    glib_ffi::g_hash_table_iter_init(&mut iter, hash_table);
+    while glib_ffi::g_hash_table_iter_next(&mut iter, &mut key, &mut value) != 0 {
+        values.insert(from_glib_none(key as *const c_char), from_glib_none(value as *mut ffi::SecretValue));
+    }
+    glib_ffi::g_hash_table_unref(hash_table);
+    values
+}
+
+fn to_glib_path_array(paths: &[String]) -> (Vec<CString>, Vec<*const c_char>) {
+    let strings: Vec<CString> = paths.iter().map(|path| CString::new(path.as_str()).unwrap()).collect();
+    let mut pointers: Vec<*const c_char> = strings.iter().map(|path| path.as_ptr()).collect();
+    pointers.push(null());
+    (strings, pointers)
+}
+
+unsafe extern "C" fn service_clear_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_clear_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_create_collection_dbus_path_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_create_collection_dbus_path_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<String, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_create_item_dbus_path_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_create_item_dbus_path_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<String, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_delete_item_dbus_path_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_delete_item_dbus_path_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_ensure_session_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    ffi::secret_service_ensure_session_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            let algorithms: Option<String> = from_glib_none(ffi::secret_service_get_session_algorithms(this as *mut _));
+            Ok(algorithms.unwrap_or_default())
+        };
+    let f: &Box<Fn(Result<String, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_get_secret_for_dbus_path_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_get_secret_for_dbus_path_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<Option<Value>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_get_secrets_for_dbus_paths_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_get_secrets_for_dbus_paths_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_value_hash_map(result))
+        };
+    let f: &Box<Fn(Result<HashMap<String, Value>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
 }
 
 unsafe extern "C" fn service_get_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
@@ -61,7 +813,9 @@ unsafe extern "C" fn service_get_trampoline(_this: *mut gobject_ffi::GObject, re
             Err(from_glib_full(error))
         }
         else {
-            Ok(from_glib_none(result))
+            let service = from_glib_full(result);
+            mark_shared(&service);
+            Ok(service)
         };
     let f: &Box<Fn(Result<Service, error::Error>) + 'static> = &*(f as *const _);
     f(value)
@@ -81,3 +835,189 @@ unsafe extern "C" fn service_load_collections_trampoline(this: *mut gobject_ffi:
     let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
     f(value)
 }
+
+unsafe extern "C" fn service_lock_dbus_paths_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut changed = null_mut();
+    let mut error = null_mut();
+    ffi::secret_service_lock_dbus_paths_finish(this as *mut _, result, &mut changed, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_path_array(changed))
+        };
+    let f: &Box<Fn(Result<Vec<String>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_lock_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut changed = null_mut();
+    let mut error = null_mut();
+    ffi::secret_service_lock_finish(this as *mut _, result, &mut changed, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_proxy_list(changed))
+        };
+    let f: &Box<Fn(Result<Vec<String>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_lookup_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_lookup_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<Option<Value>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_open_trampoline(_this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_open_finish(result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<Service, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_read_alias_dbus_path_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_read_alias_dbus_path_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_full(result))
+        };
+    let f: &Box<Fn(Result<Option<String>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_search_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_search_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_item_list(result))
+        };
+    let f: &Box<Fn(Result<Vec<Item>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_search_for_dbus_paths_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut unlocked = null_mut();
+    let mut locked = null_mut();
+    let mut error = null_mut();
+    ffi::secret_service_search_for_dbus_paths_finish(this as *mut _, result, &mut unlocked, &mut locked, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok((from_glib_path_array(unlocked), from_glib_path_array(locked)))
+        };
+    let f: &Box<Fn(Result<(Vec<String>, Vec<String>), error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_set_alias_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_set_alias_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_set_alias_to_dbus_path_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_set_alias_to_dbus_path_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_store_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut error = null_mut();
+    let result = ffi::secret_service_store_finish(this as *mut _, result, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(FromGlib::from_glib(result))
+        };
+    let f: &Box<Fn(Result<bool, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_unlock_dbus_paths_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut changed = null_mut();
+    let mut error = null_mut();
+    ffi::secret_service_unlock_dbus_paths_finish(this as *mut _, result, &mut changed, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_path_array(changed))
+        };
+    let f: &Box<Fn(Result<Vec<String>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
+
+unsafe extern "C" fn service_unlock_trampoline(this: *mut gobject_ffi::GObject, result: *mut gio_sys::GAsyncResult, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let mut changed = null_mut();
+    let mut error = null_mut();
+    ffi::secret_service_unlock_finish(this as *mut _, result, &mut changed, &mut error);
+    let value =
+        if !error.is_null() {
+            Err(from_glib_full(error))
+        }
+        else {
+            Ok(from_glib_proxy_list(changed))
+        };
+    let f: &Box<Fn(Result<Vec<String>, error::Error>) + 'static> = &*(f as *const _);
+    f(value)
+}
@@ -0,0 +1,101 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::ptr::null_mut;
+
+use glib::translate::ToGlibPtr;
+use glib_ffi;
+use gobject_ffi;
+use libc::c_char;
+
+use PasswordError::{self, InsecureSession};
+use Service;
+use self::SessionPolicy::*;
+
+const SESSION_POLICY_KEY: &'static [u8] = b"secret-rs-session-policy\0";
+
+pub const ENCRYPTED_SESSION_ALGORITHMS: &'static str = "dh-ietf1024-sha256-aes128-cbc-pkcs7";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SessionPolicy {
+    Any,
+    RequireEncrypted,
+}
+
+impl SessionPolicy {
+    pub fn check(&self, algorithms: &str) -> Result<(), PasswordError> {
+        match *self {
+            RequireEncrypted if algorithms != ENCRYPTED_SESSION_ALGORITHMS => Err(InsecureSession(algorithms.to_string())),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Any
+    }
+}
+
+impl Service {
+    pub fn get_session_policy(&self) -> SessionPolicy {
+        let policy = unsafe { gobject_ffi::g_object_get_data(self.to_glib_none().0 as *mut _, SESSION_POLICY_KEY.as_ptr() as *const c_char) };
+        if policy.is_null() {
+            Any
+        }
+        else {
+            RequireEncrypted
+        }
+    }
+
+    // Only the policy requiring encryption needs to be stored, so it is kept as a non-null pointer.
+    pub fn set_session_policy(&self, policy: SessionPolicy) {
+        let data =
+            match policy {
+                Any => null_mut(),
+                RequireEncrypted => 1 as glib_ffi::gpointer,
+            };
+        unsafe {
+            gobject_ffi::g_object_set_data(self.to_glib_none().0 as *mut _, SESSION_POLICY_KEY.as_ptr() as *const c_char, data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use PasswordError::InsecureSession;
+    use super::{ENCRYPTED_SESSION_ALGORITHMS, SessionPolicy};
+
+    #[test]
+    fn any_accepts_plain_sessions() {
+        assert!(SessionPolicy::Any.check("plain").is_ok());
+        assert!(SessionPolicy::Any.check(ENCRYPTED_SESSION_ALGORITHMS).is_ok());
+    }
+
+    #[test]
+    fn require_encrypted_rejects_plain_sessions() {
+        assert!(SessionPolicy::RequireEncrypted.check(ENCRYPTED_SESSION_ALGORITHMS).is_ok());
+        match SessionPolicy::RequireEncrypted.check("plain") {
+            Err(InsecureSession(algorithms)) => assert_eq!(algorithms, "plain"),
+            _ => panic!("a plain session was accepted"),
+        }
+    }
+}
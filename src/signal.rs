@@ -0,0 +1,69 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::ffi::CString;
+use std::mem::transmute;
+
+use glib_ffi;
+use gobject_ffi::{self, GCallback, GObject, GParamSpec};
+use libc::c_ulong;
+
+pub struct SignalHandler {
+    handler_id: c_ulong,
+    object: *mut GObject,
+}
+
+impl Drop for SignalHandler {
+    fn drop(&mut self) {
+        unsafe {
+            gobject_ffi::g_signal_handler_disconnect(self.object as *mut _, self.handler_id);
+            gobject_ffi::g_object_unref(self.object as *mut _);
+        }
+    }
+}
+
+pub unsafe fn connect<T: ?Sized>(object: *mut GObject, signal: &str, trampoline: GCallback, callback: Box<T>) -> SignalHandler {
+    let signal = CString::new(signal).unwrap();
+    let f: Box<Box<T>> = Box::new(callback);
+    let user_data: glib_ffi::gpointer = Box::into_raw(f) as *mut _;
+    let handler_id = gobject_ffi::g_signal_connect_data(object as *mut _, signal.as_ptr(), trampoline, user_data,
+        Some(destroy_closure::<T>), gobject_ffi::GConnectFlags::empty());
+    gobject_ffi::g_object_ref(object as *mut _);
+    SignalHandler {
+        handler_id: handler_id,
+        object: object,
+    }
+}
+
+pub unsafe fn connect_notify(object: *mut GObject, property: &str, callback: Box<Fn(*mut GObject) + 'static>) -> SignalHandler {
+    let trampoline: GCallback = transmute(notify_trampoline as usize);
+    connect(object, &format!("notify::{}", property), trampoline, callback)
+}
+
+unsafe extern "C" fn destroy_closure<T: ?Sized>(f: glib_ffi::gpointer, _closure: *mut gobject_ffi::GClosure) {
+    Box::<Box<T>>::from_raw(f as *mut _);
+}
+
+unsafe extern "C" fn notify_trampoline(this: *mut GObject, _pspec: *mut GParamSpec, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let f: &Box<Fn(*mut GObject) + 'static> = &*(f as *const _);
+    f(this)
+}
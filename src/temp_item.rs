@@ -0,0 +1,56 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::ops::Deref;
+
+use Item;
+
+pub struct TempItem {
+    item: Option<Item>,
+}
+
+impl TempItem {
+    pub fn new(item: Item) -> Self {
+        TempItem {
+            item: Some(item),
+        }
+    }
+
+    pub fn persist(mut self) -> Item {
+        self.item.take().unwrap()
+    }
+}
+
+impl Deref for TempItem {
+    type Target = Item;
+
+    fn deref(&self) -> &Item {
+        self.item.as_ref().unwrap()
+    }
+}
+
+impl Drop for TempItem {
+    fn drop(&mut self) {
+        if let Some(item) = self.item.take() {
+            let _ = item.delete_sync();
+        }
+    }
+}
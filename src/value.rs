@@ -0,0 +1,47 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::ffi::CString;
+use std::slice;
+
+use ffi;
+use glib::translate::{ToGlibPtr, from_glib_full};
+
+use Value;
+
+impl Value {
+    pub fn new(secret: &[u8], content_type: &str) -> Self {
+        let content_type = CString::new(content_type).unwrap();
+        let value = unsafe { ffi::secret_value_new(secret.as_ptr() as *const _, secret.len() as isize, content_type.as_ptr()) };
+        unsafe { from_glib_full(value) }
+    }
+
+    pub fn get(&self) -> Vec<u8> {
+        let mut length = 0;
+        let secret = unsafe { ffi::secret_value_get(self.to_glib_none().0, &mut length) };
+        if secret.is_null() {
+            vec![]
+        }
+        else {
+            unsafe { slice::from_raw_parts(secret as *const u8, length as usize).to_vec() }
+        }
+    }
+}
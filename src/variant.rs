@@ -0,0 +1,112 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::collections::HashMap;
+use std::ffi::CString;
+
+use glib::translate::{ToGlibPtr, from_glib_full, from_glib_none};
+use glib_ffi::{self, GHashTable, g_hash_table_insert, g_hash_table_new, g_str_equal, g_str_hash};
+
+glib_wrapper! {
+    pub struct Variant(Shared<glib_ffi::GVariant>);
+
+    match fn {
+        ref => |ptr| glib_ffi::g_variant_ref_sink(ptr),
+        unref => |ptr| glib_ffi::g_variant_unref(ptr),
+    }
+}
+
+impl Variant {
+    pub fn get_child_value(&self, index: usize) -> Variant {
+        unsafe { from_glib_full(glib_ffi::g_variant_get_child_value(self.to_glib_none().0, index)) }
+    }
+
+    pub fn get_str(&self) -> Option<String> {
+        unsafe {
+            let variant = self.to_glib_none().0;
+            if glib_ffi::g_variant_is_of_type(variant, b"s\0".as_ptr() as *const _) != 0 ||
+                glib_ffi::g_variant_is_of_type(variant, b"o\0".as_ptr() as *const _) != 0
+            {
+                let mut length = 0;
+                Some(from_glib_none(glib_ffi::g_variant_get_string(variant, &mut length)))
+            }
+            else {
+                None
+            }
+        }
+    }
+
+    pub fn get_type_string(&self) -> String {
+        unsafe { from_glib_none(glib_ffi::g_variant_get_type_string(self.to_glib_none().0)) }
+    }
+
+    pub fn print(&self) -> String {
+        unsafe { from_glib_full(glib_ffi::g_variant_print(self.to_glib_none().0, 1)) }
+    }
+}
+
+impl<'a> From<&'a str> for Variant {
+    fn from(value: &'a str) -> Self {
+        let value = CString::new(value).unwrap();
+        unsafe { from_glib_full(glib_ffi::g_variant_ref_sink(glib_ffi::g_variant_new_string(value.as_ptr()))) }
+    }
+}
+
+impl From<String> for Variant {
+    fn from(value: String) -> Self {
+        Variant::from(value.as_str())
+    }
+}
+
+impl From<bool> for Variant {
+    fn from(value: bool) -> Self {
+        unsafe { from_glib_full(glib_ffi::g_variant_ref_sink(glib_ffi::g_variant_new_boolean(value as i32))) }
+    }
+}
+
+impl From<i32> for Variant {
+    fn from(value: i32) -> Self {
+        unsafe { from_glib_full(glib_ffi::g_variant_ref_sink(glib_ffi::g_variant_new_int32(value))) }
+    }
+}
+
+impl From<u32> for Variant {
+    fn from(value: u32) -> Self {
+        unsafe { from_glib_full(glib_ffi::g_variant_ref_sink(glib_ffi::g_variant_new_uint32(value))) }
+    }
+}
+
+impl From<u64> for Variant {
+    fn from(value: u64) -> Self {
+        unsafe { from_glib_full(glib_ffi::g_variant_ref_sink(glib_ffi::g_variant_new_uint64(value))) }
+    }
+}
+
+pub unsafe fn to_glib_variant_hash_map(hash_map: &HashMap<String, Variant>) -> (Vec<CString>, *mut GHashTable) {
+    let result = g_hash_table_new(Some(g_str_hash), Some(g_str_equal));
+    let mut strings = vec![];
+    for (key, value) in hash_map {
+        let key = CString::new(key.clone()).unwrap();
+        g_hash_table_insert(result, key.as_ptr() as *mut _, value.to_glib_none().0 as *mut _);
+        strings.push(key);
+    }
+    (strings, result)
+}
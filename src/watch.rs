@@ -0,0 +1,143 @@
+/*
+ * Copyright (c) 2016 Boucher, Antoni <bouanto@zoho.com>
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy of
+ * this software and associated documentation files (the "Software"), to deal in
+ * the Software without restriction, including without limitation the rights to
+ * use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+ * the Software, and to permit persons to whom the Software is furnished to do so,
+ * subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+ * FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+ * COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+ * IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+ * CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+ */
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use gio_sys;
+use glib::error;
+use glib::translate::{ToGlibPtr, from_glib_none};
+use glib_ffi;
+use gobject_ffi;
+use libc::{c_char, c_uint};
+
+use connection::new_for_name;
+use DBusConnection;
+use Service;
+
+const SHARED_KEY: &'static [u8] = b"secret-rs-shared\0";
+
+struct WatchState {
+    appeared: Rc<Fn(Result<Service, error::Error>) + 'static>,
+    connection: DBusConnection,
+    name: String,
+    reconnect: Cell<bool>,
+    service: Rc<RefCell<Service>>,
+    shared: bool,
+    vanished: Box<Fn() + 'static>,
+}
+
+pub struct ServiceWatch {
+    watcher_id: c_uint,
+}
+
+impl Drop for ServiceWatch {
+    fn drop(&mut self) {
+        unsafe { gio_sys::g_bus_unwatch_name(self.watcher_id) };
+    }
+}
+
+impl Service {
+    // The name is watched on the connection of the service, so a service reached through a
+    // peer-to-peer connection, which has no bus name, cannot be watched.
+    pub fn watch<A, V>(&self, appeared: A, vanished: V) -> Option<ServiceWatch>
+        where A: Fn(Result<Service, error::Error>) + 'static,
+              V: Fn() + 'static,
+    {
+        let proxy = self.to_glib_none().0 as *mut gio_sys::GDBusProxy;
+        let name = unsafe { gio_sys::g_dbus_proxy_get_name(proxy) };
+        if name.is_null() {
+            return None;
+        }
+        let connection = unsafe { gio_sys::g_dbus_proxy_get_connection(proxy) };
+        let state = Box::new(WatchState {
+            appeared: Rc::new(appeared),
+            connection: unsafe { from_glib_none(connection) },
+            name: unsafe { from_glib_none(name) },
+            reconnect: Cell::new(false),
+            service: Rc::new(RefCell::new(self.clone())),
+            shared: is_shared(self),
+            vanished: Box::new(vanished),
+        });
+        let user_data: glib_ffi::gpointer = Box::into_raw(state) as *mut _;
+        let watcher_id = unsafe {
+            gio_sys::g_bus_watch_name_on_connection(connection, name, gio_sys::G_BUS_NAME_WATCHER_FLAGS_NONE,
+                Some(name_appeared_trampoline), Some(name_vanished_trampoline), user_data, Some(destroy_state))
+        };
+        Some(ServiceWatch {
+            watcher_id: watcher_id,
+        })
+    }
+}
+
+unsafe extern "C" fn destroy_state(f: glib_ffi::gpointer) {
+    Box::<WatchState>::from_raw(f as *mut _);
+}
+
+fn is_shared(service: &Service) -> bool {
+    unsafe { !gobject_ffi::g_object_get_data(service.to_glib_none().0 as *mut _, SHARED_KEY.as_ptr() as *const c_char).is_null() }
+}
+
+// libsecret does not expose its shared service, so the one returned by Service::get is marked to
+// be told apart from the private ones.
+pub fn mark_shared(service: &Service) {
+    unsafe {
+        gobject_ffi::g_object_set_data(service.to_glib_none().0 as *mut _, SHARED_KEY.as_ptr() as *const c_char, 1 as glib_ffi::gpointer);
+    }
+}
+
+unsafe extern "C" fn name_appeared_trampoline(_connection: *mut gio_sys::GDBusConnection, _name: *const c_char, _name_owner: *const c_char, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let state: &WatchState = &*(f as *const _);
+    if !state.reconnect.get() {
+        return (state.appeared)(Ok(state.service.borrow().clone()));
+    }
+    state.reconnect.set(false);
+    // The proxies of the previous owner are stale, so the service is acquired again the same way
+    // it was first: the shared one again, or a new private one on the same connection.
+    let appeared = state.appeared.clone();
+    let service = state.service.clone();
+    let flags = service.borrow().get_flags();
+    let reacquired = move |result: Result<Service, error::Error>| {
+        if let Ok(ref new_service) = result {
+            *service.borrow_mut() = new_service.clone();
+        }
+        appeared(result)
+    };
+    if state.shared {
+        Service::get_with_flags(flags, reacquired);
+    }
+    else {
+        new_for_name(&state.connection, &state.name, flags, reacquired);
+    }
+}
+
+unsafe extern "C" fn name_vanished_trampoline(_connection: *mut gio_sys::GDBusConnection, _name: *const c_char, f: glib_ffi::gpointer) {
+    callback_guard!();
+    let state: &WatchState = &*(f as *const _);
+    state.reconnect.set(true);
+    // Drop the shared service and its stale proxies so that the next operation connects to the new
+    // daemon; a private service is only replaced for this watch.
+    if state.shared {
+        Service::disconnect();
+    }
+    (state.vanished)()
+}